@@ -1,7 +1,9 @@
+use std::collections::HashMap;
 use std::convert::From;
 
 use clap::ArgMatches;
 
+#[allow(deprecated)]
 impl Default for Diff2HtmlConfig {
     fn default() -> Diff2HtmlConfig {
         Diff2HtmlConfig {
@@ -20,6 +22,16 @@ impl Default for Diff2HtmlConfig {
             max_line_length_highlight: 10000,
             word_by_word: true,
             char_by_char: false,
+            diff_algorithm: "myers".to_owned(),
+            ignore_whitespace: "none".to_owned(),
+            ignore_case: false,
+            hide_whitespace_changes: false,
+            src_prefix: None,
+            dst_prefix: None,
+            no_prefix: false,
+            language_extensions: HashMap::new(),
+            language_filenames: HashMap::new(),
+            language_interpreters: HashMap::new(),
             trail: None,
         }
     }
@@ -38,10 +50,30 @@ pub struct Diff2HtmlConfig {
     pub matching_max_comparisons: usize,
     pub file: Option<String>,
     pub format: String,
+    #[deprecated(note = "combined diffs are detected per file, see `parse::File::is_combined`")]
     pub is_combined: bool,
     pub max_line_length_highlight: usize,
     pub word_by_word: bool,
     pub char_by_char: bool,
+    // One of "myers", "patience", "histogram" or "lcs". The quadratic "lcs"
+    // is only fit for short lines, so the linear-space "myers" is the default.
+    pub diff_algorithm: String,
+    // Changes left out of intraline highlighting and line matching: one of
+    // "none", "all", "change" or "eol", like git's `-w`, `-b` and
+    // `--ignore-space-at-eol`.
+    pub ignore_whitespace: String,
+    pub ignore_case: bool,
+    // Leaves out changes that only touch whitespace, and the files left
+    // without changes.
+    pub hide_whitespace_changes: bool,
+    pub src_prefix: Option<String>,
+    pub dst_prefix: Option<String>,
+    pub no_prefix: bool,
+    // Languages added to the built-in ones, by extension, exact file name and
+    // shebang interpreter.
+    pub language_extensions: HashMap<String, String>,
+    pub language_filenames: HashMap<String, String>,
+    pub language_interpreters: HashMap<String, String>,
     pub trail: Option<Vec<String>>,
 }
 
@@ -108,6 +140,35 @@ impl<'a> From<ArgMatches<'a>> for Diff2HtmlConfig {
                 .expect("Match words threshold is not in unsigned integer format.");
         }
 
+        // diff_algorithm
+        if let Some(diff_algorithm) = matches.value_of("diffAlgorithm") {
+            config.diff_algorithm = diff_algorithm.to_owned();
+        }
+
+        // ignore_whitespace
+        if let Some(ignore_whitespace) = matches.value_of("ignoreWhitespace") {
+            config.ignore_whitespace = ignore_whitespace.to_owned();
+        }
+
+        // ignore_case
+        config.ignore_case = matches.is_present("ignoreCase");
+
+        // hide_whitespace_changes
+        config.hide_whitespace_changes = matches.is_present("hideWhitespaceChanges");
+
+        // src_prefix
+        if let Some(src_prefix) = matches.value_of("srcPrefix") {
+            config.src_prefix = Some(src_prefix.to_owned());
+        }
+
+        // dst_prefix
+        if let Some(dst_prefix) = matches.value_of("dstPrefix") {
+            config.dst_prefix = Some(dst_prefix.to_owned());
+        }
+
+        // no_prefix
+        config.no_prefix = matches.is_present("noPrefix");
+
         config.trail = matches
             .values_of("trail")
             .map(|v| v.map(|v| v.to_owned()).collect());
@@ -1,4 +1,6 @@
 use std::cmp::max;
+use std::collections::HashMap;
+use std::hash::Hash;
 
 use difference::Difference;
 
@@ -17,6 +19,28 @@ pub fn diff(orig: &str, edit: &str, split: &SplitType) -> (i32, Vec<Difference>)
     (ch.distance, ch.diffs)
 }
 
+#[derive(Clone, Debug, PartialEq)]
+pub enum Algorithm {
+    // the quadratic table of `lcs`, only fit for short inputs
+    Lcs,
+    Myers,
+    Patience,
+    Histogram,
+}
+
+impl Algorithm {
+    // the algorithm of a `diff_algorithm` config value, myers, the default,
+    // when unknown
+    pub fn from_name(name: &str) -> Algorithm {
+        match name {
+            "lcs" => Algorithm::Lcs,
+            "patience" => Algorithm::Patience,
+            "histogram" => Algorithm::Histogram,
+            _ => Algorithm::Myers,
+        }
+    }
+}
+
 pub struct Changeset {
     pub diffs: Vec<Difference>,
     pub split: SplitType,
@@ -24,16 +48,63 @@ pub struct Changeset {
 }
 
 impl Changeset {
+    // compares with `lcs` as it always did, `with_algorithm` picks another;
+    // inputs too long for its table are compared with myers
     pub fn new(orig: &str, edit: &str, split: &SplitType) -> Changeset {
-        let (dist, common) = lcs(orig, edit, split);
+        Changeset::with_algorithm(orig, edit, split, &Algorithm::Lcs)
+    }
+
+    pub fn with_algorithm(
+        orig: &str,
+        edit: &str,
+        split: &SplitType,
+        algorithm: &Algorithm,
+    ) -> Changeset {
+        let a = tokenize(orig, split);
+        let b = tokenize(edit, split);
+        let algorithm = if *algorithm == Algorithm::Lcs && a.len() * b.len() > MAX_LCS_TABLE {
+            &Algorithm::Myers
+        } else {
+            algorithm
+        };
+
+        // `merge` splits lines of the common part that `lcs` joins without
+        // a separator, so lines are matched as tokens.
+        if *algorithm == Algorithm::Lcs && *split != SplitType::Line {
+            let (dist, common) = lcs(orig, edit, split);
+            return Changeset {
+                diffs: merge(orig, edit, &common, split),
+                split: split.to_owned(),
+                distance: dist,
+            };
+        }
+
+        let matches = diff_tokens(&a, &b, algorithm);
+
         Changeset {
-            diffs: merge(orig, edit, &common, split),
+            diffs: to_differences(&a, &b, &matches, split),
             split: split.to_owned(),
-            distance: dist,
+            distance: (a.len() + b.len() - 2 * matches.len()) as i32,
         }
     }
 }
 
+// cells of the `lcs` table, a few tens of megabytes, beyond which myers is
+// used instead
+static MAX_LCS_TABLE: usize = 4_000_000;
+
+// the pairs of indices of the tokens both inputs keep, in order
+fn diff_tokens<T: Eq + Hash>(a: &[T], b: &[T], algorithm: &Algorithm) -> Vec<(usize, usize)> {
+    let mut matches = Vec::new();
+    match algorithm {
+        Algorithm::Lcs => matches = lcs_tokens(a, b),
+        Algorithm::Myers => myers(a, b, 0, 0, &mut matches),
+        Algorithm::Patience => patience(a, b, 0, 0, &mut matches),
+        Algorithm::Histogram => histogram(a, b, 0, 0, &mut matches),
+    }
+    matches
+}
+
 fn strsplit<'a>(s: &'a str, split: &str) -> Vec<&'a str> {
     let mut si = s.split(split);
     if split == "" {
@@ -84,6 +155,19 @@ pub fn lcs(orig: &str, edit: &str, split: &SplitType) -> (i32, String) {
         SplitType::SmartWord => (smartsplit(orig), smartsplit(edit)),
     };
 
+    let lcs = lcs_tokens(&a, &b)
+        .into_iter()
+        .map(|(i, _)| a[i])
+        .collect::<Vec<&str>>();
+    (
+        (a.len() + b.len() - 2 * lcs.len()) as i32,
+        lcs.join(separator(split)),
+    )
+}
+
+// the table of `lcs`, walked back into the pairs of matching tokens
+#[allow(non_snake_case)]
+fn lcs_tokens<T: Eq>(a: &[T], b: &[T]) -> Vec<(usize, usize)> {
     let N = a.len();
     let M = b.len();
 
@@ -119,7 +203,7 @@ pub fn lcs(orig: &str, edit: &str, split: &SplitType) -> (i32, String) {
         let ui = i as usize;
         let uj = j as usize;
         if a[ui] == b[uj] {
-            lcs.push(a[ui]);
+            lcs.push((ui, uj));
             i -= 1;
             j -= 1;
         } else if j == 0 && i == 0 {
@@ -132,10 +216,7 @@ pub fn lcs(orig: &str, edit: &str, split: &SplitType) -> (i32, String) {
     }
 
     lcs.reverse();
-    (
-        (N + M - 2 * lcs.len()) as i32,
-        lcs.join(if *split == SplitType::Word { " " } else { "" }),
-    )
+    lcs
 }
 
 // merges the changes from two strings, given a common substring
@@ -184,7 +265,7 @@ pub fn merge(orig: &str, edit: &str, common: &str, split: &SplitType) -> Vec<Dif
             c.next();
         }
         if !same.is_empty() {
-            let joined = same.join(if *split == SplitType::Word { " " } else { "" });
+            let joined = same.join(separator(split));
             if split != &SplitType::Character || joined != "" {
                 ret.push(Difference::Same(joined));
             }
@@ -195,11 +276,7 @@ pub fn merge(orig: &str, edit: &str, common: &str, split: &SplitType) -> Vec<Dif
             rem.push(l.next().unwrap());
         }
         if !rem.is_empty() {
-            ret.push(Difference::Rem(rem.join(if *split == SplitType::Word {
-                " "
-            } else {
-                ""
-            })));
+            ret.push(Difference::Rem(rem.join(separator(split))));
         }
 
         let mut add = Vec::new();
@@ -207,13 +284,574 @@ pub fn merge(orig: &str, edit: &str, common: &str, split: &SplitType) -> Vec<Dif
             add.push(r.next().unwrap());
         }
         if !add.is_empty() {
-            ret.push(Difference::Add(add.join(if *split == SplitType::Word {
-                " "
+            ret.push(Difference::Add(add.join(separator(split))));
+        }
+    }
+
+    ret
+}
+
+// joins the parts of a split back together, as `lcs` and `merge` always did
+fn separator(split: &SplitType) -> &'static str {
+    if *split == SplitType::Word {
+        " "
+    } else {
+        ""
+    }
+}
+
+fn tokenize<'a>(s: &'a str, split: &SplitType) -> Vec<&'a str> {
+    if s.is_empty() {
+        return Vec::new();
+    }
+    match split {
+        SplitType::Character => strsplit(s, ""),
+        SplitType::Word => strsplit(s, " "),
+        SplitType::Line => strsplit(s, "\n"),
+        SplitType::SmartWord => smartsplit(s),
+    }
+}
+
+// turns the matching tokens of both inputs into differences, removals
+// before additions like `merge`
+fn to_differences(
+    a: &[&str],
+    b: &[&str],
+    matches: &[(usize, usize)],
+    split: &SplitType,
+) -> Vec<Difference> {
+    let mut ret = Vec::new();
+    let push = |ret: &mut Vec<Difference>, tokens: &[&str], kind: fn(String) -> Difference| {
+        if !tokens.is_empty() {
+            ret.push(kind(tokens.join(separator(split))));
+        }
+    };
+
+    let (mut i, mut j) = (0, 0);
+    let mut same_start = 0;
+    for &(mi, mj) in matches.iter().chain(Some(&(a.len(), b.len()))) {
+        if mi > i || mj > j {
+            push(&mut ret, &a[same_start..i], Difference::Same);
+            push(&mut ret, &a[i..mi], Difference::Rem);
+            push(&mut ret, &b[j..mj], Difference::Add);
+            same_start = mi;
+        }
+        i = mi + 1;
+        j = mj + 1;
+    }
+    push(&mut ret, &a[same_start..a.len()], Difference::Same);
+
+    ret
+}
+
+// strips the common prefix and suffix, matching them, and calls `diff` on
+// what is left in between
+fn trim_common<T, F>(
+    a: &[T],
+    b: &[T],
+    a_offset: usize,
+    b_offset: usize,
+    matches: &mut Vec<(usize, usize)>,
+    diff: F,
+) where
+    T: Eq,
+    F: FnOnce(&[T], &[T], usize, usize, &mut Vec<(usize, usize)>),
+{
+    let prefix = a.iter().zip(b.iter()).take_while(|(x, y)| x == y).count();
+    let suffix = a[prefix..]
+        .iter()
+        .rev()
+        .zip(b[prefix..].iter().rev())
+        .take_while(|(x, y)| x == y)
+        .count();
+
+    matches.extend((0..prefix).map(|k| (a_offset + k, b_offset + k)));
+    let a_middle = &a[prefix..a.len() - suffix];
+    let b_middle = &b[prefix..b.len() - suffix];
+    if !a_middle.is_empty() && !b_middle.is_empty() {
+        diff(
+            a_middle,
+            b_middle,
+            a_offset + prefix,
+            b_offset + prefix,
+            matches,
+        );
+    }
+    matches.extend((0..suffix).map(|k| {
+        (
+            a_offset + a.len() - suffix + k,
+            b_offset + b.len() - suffix + k,
+        )
+    }));
+}
+
+// Myers' O(ND) algorithm in linear space: the middle snake of the shortest
+// edit script is found walking from both ends, then both halves are diffed
+//
+// This is based on the bisection of
+// https://github.com/google/diff-match-patch
+pub fn myers<T: Eq>(
+    a: &[T],
+    b: &[T],
+    a_offset: usize,
+    b_offset: usize,
+    matches: &mut Vec<(usize, usize)>,
+) {
+    trim_common(
+        a,
+        b,
+        a_offset,
+        b_offset,
+        matches,
+        |a, b, a_offset, b_offset, matches| {
+            if let Some((x, y)) = middle_snake(a, b) {
+                myers(&a[..x], &b[..y], a_offset, b_offset, matches);
+                myers(&a[x..], &b[y..], a_offset + x, b_offset + y, matches);
+            }
+        },
+    );
+}
+
+fn middle_snake<T: Eq>(a: &[T], b: &[T]) -> Option<(usize, usize)> {
+    let n = a.len() as isize;
+    let m = b.len() as isize;
+    let max_d = (n + m + 1) / 2;
+    let v_offset = max_d;
+    let v_length = 2 * max_d + 2;
+    let mut v1 = vec![-1isize; v_length as usize];
+    let mut v2 = vec![-1isize; v_length as usize];
+    v1[(v_offset + 1) as usize] = 0;
+    v2[(v_offset + 1) as usize] = 0;
+
+    let delta = n - m;
+    // with an odd delta the forward path meets the reverse one
+    let front = delta % 2 != 0;
+    let (mut k1_start, mut k1_end, mut k2_start, mut k2_end) = (0, 0, 0, 0);
+
+    for d in 0..max_d {
+        let mut k1 = -d + k1_start;
+        while k1 <= d - k1_end {
+            let k1_offset = (v_offset + k1) as usize;
+            let mut x1 = if k1 == -d || (k1 != d && v1[k1_offset - 1] < v1[k1_offset + 1]) {
+                v1[k1_offset + 1]
             } else {
-                ""
-            })));
+                v1[k1_offset - 1] + 1
+            };
+            let mut y1 = x1 - k1;
+            while x1 < n && y1 < m && a[x1 as usize] == b[y1 as usize] {
+                x1 += 1;
+                y1 += 1;
+            }
+            v1[k1_offset] = x1;
+            if x1 > n {
+                k1_end += 2;
+            } else if y1 > m {
+                k1_start += 2;
+            } else if front {
+                let k2_offset = v_offset + delta - k1;
+                if k2_offset >= 0 && k2_offset < v_length && v2[k2_offset as usize] != -1 {
+                    let x2 = n - v2[k2_offset as usize];
+                    if x1 >= x2 {
+                        return Some((x1 as usize, y1 as usize));
+                    }
+                }
+            }
+            k1 += 2;
+        }
+
+        let mut k2 = -d + k2_start;
+        while k2 <= d - k2_end {
+            let k2_offset = (v_offset + k2) as usize;
+            let mut x2 = if k2 == -d || (k2 != d && v2[k2_offset - 1] < v2[k2_offset + 1]) {
+                v2[k2_offset + 1]
+            } else {
+                v2[k2_offset - 1] + 1
+            };
+            let mut y2 = x2 - k2;
+            while x2 < n && y2 < m && a[(n - x2 - 1) as usize] == b[(m - y2 - 1) as usize] {
+                x2 += 1;
+                y2 += 1;
+            }
+            v2[k2_offset] = x2;
+            if x2 > n {
+                k2_end += 2;
+            } else if y2 > m {
+                k2_start += 2;
+            } else if !front {
+                let k1_offset = v_offset + delta - k2;
+                if k1_offset >= 0 && k1_offset < v_length && v1[k1_offset as usize] != -1 {
+                    let x1 = v1[k1_offset as usize];
+                    let y1 = v_offset + x1 - k1_offset;
+                    if x1 >= n - x2 {
+                        return Some((x1 as usize, y1 as usize));
+                    }
+                }
+            }
+            k2 += 2;
         }
     }
 
+    // nothing in common
+    None
+}
+
+// levels `patience` and `histogram` recurse into before leaving what is left
+// to myers, which bounds their stack and the tables they build at every level
+static MAX_DEPTH: usize = 64;
+
+// patience diff: tokens found exactly once in both inputs are matched when
+// they keep their order, and the gaps between them are diffed again, which
+// keeps unique lines like function signatures aligned
+pub fn patience<T: Eq + Hash>(
+    a: &[T],
+    b: &[T],
+    a_offset: usize,
+    b_offset: usize,
+    matches: &mut Vec<(usize, usize)>,
+) {
+    patience_at(a, b, a_offset, b_offset, matches, 0);
+}
+
+fn patience_at<T: Eq + Hash>(
+    a: &[T],
+    b: &[T],
+    a_offset: usize,
+    b_offset: usize,
+    matches: &mut Vec<(usize, usize)>,
+    depth: usize,
+) {
+    if depth >= MAX_DEPTH {
+        myers(a, b, a_offset, b_offset, matches);
+        return;
+    }
+
+    trim_common(
+        a,
+        b,
+        a_offset,
+        b_offset,
+        matches,
+        |a, b, a_offset, b_offset, matches| {
+            let mut counts: HashMap<&T, (usize, usize, usize, usize)> = HashMap::new();
+            for (i, token) in a.iter().enumerate() {
+                let entry = counts.entry(token).or_insert((0, 0, 0, 0));
+                entry.0 += 1;
+                entry.1 = i;
+            }
+            for (j, token) in b.iter().enumerate() {
+                if let Some(entry) = counts.get_mut(token) {
+                    entry.2 += 1;
+                    entry.3 = j;
+                }
+            }
+            let mut unique = counts
+                .values()
+                .filter(|(a_count, _, b_count, _)| *a_count == 1 && *b_count == 1)
+                .map(|(_, i, _, j)| (*i, *j))
+                .collect::<Vec<(usize, usize)>>();
+            unique.sort();
+
+            let anchors = longest_increasing(&unique);
+            if anchors.is_empty() {
+                myers(a, b, a_offset, b_offset, matches);
+                return;
+            }
+
+            let (mut i, mut j) = (0, 0);
+            for (ai, bj) in anchors {
+                patience_at(
+                    &a[i..ai],
+                    &b[j..bj],
+                    a_offset + i,
+                    b_offset + j,
+                    matches,
+                    depth + 1,
+                );
+                matches.push((a_offset + ai, b_offset + bj));
+                i = ai + 1;
+                j = bj + 1;
+            }
+            patience_at(
+                &a[i..],
+                &b[j..],
+                a_offset + i,
+                b_offset + j,
+                matches,
+                depth + 1,
+            );
+        },
+    );
+}
+
+// the longest run of pairs, sorted by their first index, whose second index
+// increases too, found by patience sorting
+fn longest_increasing(pairs: &[(usize, usize)]) -> Vec<(usize, usize)> {
+    // index in `pairs` of the top of every pile, and of the top of the
+    // previous pile when each pair was placed
+    let mut piles: Vec<usize> = Vec::new();
+    let mut previous: Vec<Option<usize>> = Vec::with_capacity(pairs.len());
+    for (k, pair) in pairs.iter().enumerate() {
+        let pile = match piles.binary_search_by(|top| pairs[*top].1.cmp(&pair.1)) {
+            Ok(pile) | Err(pile) => pile,
+        };
+        previous.push(if pile > 0 {
+            Some(piles[pile - 1])
+        } else {
+            None
+        });
+        if pile == piles.len() {
+            piles.push(k);
+        } else {
+            piles[pile] = k;
+        }
+    }
+
+    let mut ret = Vec::new();
+    let mut k = piles.last().cloned();
+    while let Some(index) = k {
+        ret.push(pairs[index]);
+        k = previous[index];
+    }
+    ret.reverse();
     ret
 }
+
+// tokens found more often than this are left to myers by `histogram`
+static MAX_CHAIN: usize = 64;
+
+// histogram diff, as git does it: the common region around the token that
+// is rarest in the old input is matched and both sides of it are diffed
+// again, falling back to myers when every token is frequent
+pub fn histogram<T: Eq + Hash>(
+    a: &[T],
+    b: &[T],
+    a_offset: usize,
+    b_offset: usize,
+    matches: &mut Vec<(usize, usize)>,
+) {
+    histogram_at(a, b, a_offset, b_offset, matches, 0);
+}
+
+fn histogram_at<T: Eq + Hash>(
+    a: &[T],
+    b: &[T],
+    a_offset: usize,
+    b_offset: usize,
+    matches: &mut Vec<(usize, usize)>,
+    depth: usize,
+) {
+    if depth >= MAX_DEPTH {
+        myers(a, b, a_offset, b_offset, matches);
+        return;
+    }
+
+    trim_common(
+        a,
+        b,
+        a_offset,
+        b_offset,
+        matches,
+        |a, b, a_offset, b_offset, matches| {
+            let mut occurrences: HashMap<&T, Vec<usize>> = HashMap::new();
+            for (i, token) in a.iter().enumerate() {
+                occurrences.entry(token).or_default().push(i);
+            }
+
+            // start in both inputs, length and lowest token count of the region
+            let mut best: Option<(usize, usize, usize, usize)> = None;
+            let mut j = 0;
+            while j < b.len() {
+                let mut next = j + 1;
+                if let Some(positions) = occurrences.get(&b[j]) {
+                    let best_count = best.map_or(MAX_CHAIN + 1, |region| region.3);
+                    if positions.len() <= best_count {
+                        for &i in positions.iter() {
+                            let (mut start_a, mut start_b) = (i, j);
+                            while start_a > 0 && start_b > 0 && a[start_a - 1] == b[start_b - 1] {
+                                start_a -= 1;
+                                start_b -= 1;
+                            }
+                            let (mut end_a, mut end_b) = (i + 1, j + 1);
+                            while end_a < a.len() && end_b < b.len() && a[end_a] == b[end_b] {
+                                end_a += 1;
+                                end_b += 1;
+                            }
+
+                            let count = (start_a..end_a)
+                                .map(|k| occurrences[&a[k]].len())
+                                .min()
+                                .unwrap_or(0);
+                            let is_better = match best {
+                                Some((_, _, length, best_count)) => {
+                                    count < best_count
+                                        || (count == best_count && end_a - start_a > length)
+                                }
+                                None => true,
+                            };
+                            if is_better {
+                                best = Some((start_a, start_b, end_a - start_a, count));
+                            }
+                            next = max(next, end_b);
+                        }
+                    }
+                }
+                j = next;
+            }
+
+            match best {
+                Some((start_a, start_b, length, _)) => {
+                    histogram_at(
+                        &a[..start_a],
+                        &b[..start_b],
+                        a_offset,
+                        b_offset,
+                        matches,
+                        depth + 1,
+                    );
+                    matches.extend(
+                        (0..length).map(|k| (a_offset + start_a + k, b_offset + start_b + k)),
+                    );
+                    let (end_a, end_b) = (start_a + length, start_b + length);
+                    histogram_at(
+                        &a[end_a..],
+                        &b[end_b..],
+                        a_offset + end_a,
+                        b_offset + end_b,
+                        matches,
+                        depth + 1,
+                    );
+                }
+                None => myers(a, b, a_offset, b_offset, matches),
+            }
+        },
+    );
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn chars(s: &str) -> Vec<char> {
+        s.chars().collect()
+    }
+
+    // the edit script of the matches, `=` kept, `-` removed and `+` added
+    fn script<T>(a: &[T], b: &[T], matches: &[(usize, usize)]) -> String {
+        let mut ret = String::new();
+        let (mut i, mut j) = (0, 0);
+        for &(mi, mj) in matches.iter().chain(Some(&(a.len(), b.len()))) {
+            ret.extend((i..mi).map(|_| '-'));
+            ret.extend((j..mj).map(|_| '+'));
+            if mi < a.len() {
+                ret.push('=');
+            }
+            i = mi + 1;
+            j = mj + 1;
+        }
+        ret
+    }
+
+    fn check<T: Eq + Hash>(a: &[T], b: &[T], algorithm: &Algorithm) -> String {
+        let matches = diff_tokens(a, b, algorithm);
+        for pair in matches.windows(2) {
+            assert!(pair[0].0 < pair[1].0 && pair[0].1 < pair[1].1);
+        }
+        for &(i, j) in matches.iter() {
+            assert!(a[i] == b[j]);
+        }
+        script(a, b, &matches)
+    }
+
+    #[test]
+    fn lcs_finds_the_longest_common_subsequence() {
+        let (a, b) = (chars("ABCABBA"), chars("CBABAC"));
+        assert_eq!(check(&a, &b, &Algorithm::Lcs), "-+=-=-==+");
+    }
+
+    #[test]
+    fn myers_finds_a_shortest_edit_script() {
+        // the example of Myers' paper, with an edit distance of 5
+        let (a, b) = (chars("ABCABBA"), chars("CBABAC"));
+        assert_eq!(check(&a, &b, &Algorithm::Myers), "-+=-==-=+");
+        assert_eq!(
+            check(&chars("abc"), &chars("abc"), &Algorithm::Myers),
+            "==="
+        );
+        assert_eq!(check(&chars(""), &chars("ab"), &Algorithm::Myers), "++");
+        assert_eq!(check(&chars("ab"), &chars("xy"), &Algorithm::Myers), "--++");
+    }
+
+    #[test]
+    fn patience_aligns_unique_lines() {
+        let a = ["fn a() {", "x", "}", "", "fn b() {", "y", "}"];
+        let b = ["fn b() {", "y", "}", "", "fn c() {", "z", "}"];
+        // the unique signature of `b` is kept with its body
+        assert_eq!(check(&a, &b, &Algorithm::Patience), "----==++++=");
+    }
+
+    #[test]
+    fn histogram_matches_the_rarest_region() {
+        // `b` and `}` are as rare, the longer region around `}` wins
+        let a = ["{", "a", "}", "{", "b", "}"];
+        let b = ["{", "b", "}", "{", "c", "}"];
+        assert_eq!(check(&a, &b, &Algorithm::Histogram), "=-+==-+=");
+    }
+
+    #[test]
+    fn deep_inputs_fall_back_to_myers() {
+        // every level of the recursion only matches one line
+        let a = (0..3000).collect::<Vec<usize>>();
+        let b = a.iter().rev().cloned().collect::<Vec<usize>>();
+        for algorithm in [Algorithm::Patience, Algorithm::Histogram].iter() {
+            let matches = diff_tokens(&a, &b, algorithm);
+            assert_eq!(matches.len(), 1);
+        }
+    }
+
+    #[test]
+    fn long_inputs_leave_lcs_for_myers() {
+        // a 9 million cell table
+        let orig = "ab".repeat(1500);
+        let edit = "ba".repeat(1500);
+        let changeset = Changeset::new(&orig, &edit, &SplitType::Character);
+        let myers =
+            Changeset::with_algorithm(&orig, &edit, &SplitType::Character, &Algorithm::Myers);
+        assert_eq!(changeset.diffs, myers.diffs);
+        assert_eq!(changeset.distance, 2);
+
+        assert_eq!(Algorithm::from_name("unknown"), Algorithm::Myers);
+        assert_eq!(
+            Algorithm::from_name(&crate::config::Diff2HtmlConfig::default().diff_algorithm),
+            Algorithm::Myers
+        );
+    }
+
+    #[test]
+    fn changesets_join_the_parts_of_their_split() {
+        let changeset = Changeset::new("a b c", "a x c", &SplitType::Word);
+        assert_eq!(
+            changeset.diffs,
+            vec![
+                Difference::Same("a".to_owned()),
+                Difference::Rem("b".to_owned()),
+                Difference::Add("x".to_owned()),
+                Difference::Same("c".to_owned()),
+            ]
+        );
+        assert_eq!(changeset.distance, 2);
+
+        for algorithm in [Algorithm::Myers, Algorithm::Patience, Algorithm::Histogram].iter() {
+            let changeset =
+                Changeset::with_algorithm("abcd", "abxd", &SplitType::Character, algorithm);
+            assert_eq!(
+                changeset.diffs,
+                vec![
+                    Difference::Same("ab".to_owned()),
+                    Difference::Rem("c".to_owned()),
+                    Difference::Add("x".to_owned()),
+                    Difference::Same("d".to_owned()),
+                ]
+            );
+        }
+    }
+}
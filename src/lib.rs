@@ -8,4 +8,5 @@ extern crate serde_json;
 pub mod config;
 pub mod difference;
 pub mod parse;
+pub mod patch;
 pub mod printers;
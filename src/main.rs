@@ -2,66 +2,153 @@
 
 #[macro_use]
 extern crate clap;
-#[macro_use]
-extern crate lazy_static;
-#[macro_use]
-extern crate serde;
-#[macro_use]
-extern crate serde_json;
 
 use std::fs;
-use std::io::{Read, Write};
-use std::process::Command;
+use std::io::{self, BufRead, BufReader, Cursor, Read, Write};
+use std::process::{Child, Command, Stdio};
 
 use clap::{App, AppSettings, Arg, ArgMatches};
 
-mod config;
-mod difference;
-mod parse;
-mod printers;
+use diff2html::config::Diff2HtmlConfig;
+use diff2html::parse;
+use diff2html::printers::PagePrinter;
 
-use crate::config::Diff2HtmlConfig;
-use crate::printers::PagePrinter;
+// Lines read ahead to tell plain diffs from commit series and range diffs.
+const PEEK_LINES: usize = 64;
+const PEEK_BYTES: u64 = 64 * 1024;
 
 fn main() {
-    let config: Diff2HtmlConfig = get_arg_matches().into();
-    let input = get_input(&config);
-    let output = get_output(&config, &input);
-    handle_output(&config, &output);
+    let mut config: Diff2HtmlConfig = get_arg_matches().into();
+    config.word_by_word = config.diff == "word";
+    config.char_by_char = config.diff == "char" || config.diff == "smartword";
+
+    let (input, git) = match get_input(&config) {
+        Ok(input) => input,
+        Err(error) => exit_with_error(error),
+    };
+    let mut out = io::BufWriter::new(get_output(&config));
+    let written = write_output(&config, input, &mut out).and_then(|_| out.flush());
+    // git is waited on even when writing failed, its status tells why.
+    if let Err(error) = git.map_or(Ok(()), wait_for_git).and(written) {
+        exit_with_error(error);
+    }
 }
 
-fn get_input(config: &Diff2HtmlConfig) -> String {
-    let mut input = Vec::new();
+fn exit_with_error(error: io::Error) -> ! {
+    eprintln!("diff2html: {}", error);
+    std::process::exit(1);
+}
+
+// The input, with the git process writing it when there is one.
+fn get_input(config: &Diff2HtmlConfig) -> io::Result<(Box<dyn BufRead>, Option<Child>)> {
     if config.input == "stdin" {
-        ::std::io::stdin().read_to_end(&mut input).unwrap();
+        Ok((Box::new(BufReader::new(io::stdin())), None))
     } else if config.input == "file" {
-        let trailing = config.trail.as_ref().expect("No input file specified.");
-        let file_name = trailing.get(0).expect("No input file specified.");
-        let mut file = fs::File::open(file_name).unwrap();
-        file.read_to_end(&mut input).unwrap();
+        let file_name = config
+            .trail
+            .as_ref()
+            .and_then(|trailing| trailing.first())
+            .ok_or_else(|| {
+                io::Error::new(io::ErrorKind::InvalidInput, "no input file specified")
+            })?;
+        let file = fs::File::open(file_name)
+            .map_err(|error| io::Error::new(error.kind(), format!("{}: {}", file_name, error)))?;
+        Ok((Box::new(BufReader::new(file)), None))
     } else {
-        input = get_git_diff(config);
+        let mut git = get_git_diff(config)?;
+        let stdout = git.stdout.take().unwrap();
+        Ok((Box::new(BufReader::new(stdout)), Some(git)))
     }
-    String::from_utf8_lossy(&input[..]).to_string()
 }
 
-fn get_output(config: &Diff2HtmlConfig, input: &str) -> String {
-    let mut config = config.to_owned();
-    config.word_by_word = config.diff == "word";
-    config.char_by_char = config.diff == "char" || config.diff == "smartword";
+/*
+ * Plain diffs are parsed and written file by file, so inputs of any size
+ * fit in memory. Commit series and range diffs are read whole.
+ */
+fn write_output<R: BufRead>(
+    config: &Diff2HtmlConfig,
+    mut input: R,
+    out: &mut dyn Write,
+) -> io::Result<()> {
+    let mut head = Vec::new();
+    for _ in 0..PEEK_LINES {
+        if input
+            .by_ref()
+            .take(PEEK_BYTES)
+            .read_until(b'\n', &mut head)?
+            == 0
+        {
+            break;
+        }
+    }
 
-    let files = parse::parse_diff(&input);
+    let head_text = String::from_utf8_lossy(&head).to_string();
+    if parse::is_range_diff(&head_text)
+        || parse::is_patch_series(&head_text)
+        || parse::is_git_log(&head_text)
+    {
+        input.read_to_end(&mut head)?;
+        let input = String::from_utf8_lossy(&head).to_string();
+        return writeln!(out, "{}", get_commits_output(config, &input));
+    }
 
+    let mut reader = parse::DiffReader::with_config(Cursor::new(head).chain(input), config);
     if config.format == "html" {
-        let page_printer = PagePrinter::new(config);
-        page_printer.render(&files)
+        PagePrinter::new(config.to_owned()).render_to(reader.by_ref(), out)?;
     } else {
-        serde_json::to_string(&files).unwrap()
+        write!(out, "[")?;
+        let mut is_first = true;
+        for mut file in reader.by_ref() {
+            if config.hide_whitespace_changes && parse::hide_file_whitespace_changes(&mut file) {
+                continue;
+            }
+            if !is_first {
+                write!(out, ",")?;
+            }
+            serde_json::to_writer(&mut *out, &file)?;
+            is_first = false;
+        }
+        write!(out, "]")?;
+    }
+    writeln!(out)?;
+
+    match reader.take_error() {
+        Some(error) => Err(error),
+        _ => Ok(()),
     }
 }
 
-fn handle_output(config: &Diff2HtmlConfig, output: &str) {
-    let mut out: Box<Write> = if let Some(file) = &config.file {
+fn get_commits_output(config: &Diff2HtmlConfig, input: &str) -> String {
+    let config = config.to_owned();
+
+    if parse::is_range_diff(input) {
+        let pairs = parse::parse_range_diff(input);
+        return if config.format == "html" {
+            PagePrinter::new(config).render_range_diff(&pairs)
+        } else {
+            serde_json::to_string(&pairs).unwrap()
+        };
+    }
+
+    let mut commits = if parse::is_patch_series(input) {
+        parse::parse_patch_series(input, &config)
+    } else {
+        parse::parse_log(input, &config)
+    };
+    if config.format == "html" {
+        PagePrinter::new(config).render_commits(&commits)
+    } else {
+        if config.hide_whitespace_changes {
+            for commit in commits.iter_mut() {
+                parse::hide_whitespace_changes(&mut commit.files);
+            }
+        }
+        serde_json::to_string(&commits).unwrap()
+    }
+}
+
+fn get_output(config: &Diff2HtmlConfig) -> Box<dyn Write> {
+    if let Some(file) = &config.file {
         Box::new(
             std::fs::OpenOptions::new()
                 .read(true)
@@ -77,8 +164,7 @@ fn handle_output(config: &Diff2HtmlConfig, output: &str) {
                 panic!("Invalid output type.");
             }
         }
-    };
-    writeln!(&mut out, "{}", &output).expect("Failed to write out.");
+    }
 }
 
 fn get_arg_matches() -> ArgMatches<'static> {
@@ -98,7 +184,7 @@ fn get_arg_matches() -> ArgMatches<'static> {
             Arg::with_name("style")
                 .long("style")
                 .value_name("STYLE")
-                .possible_values(&["line", "side"])
+                .possible_values(&["line", "side", "three"])
                 .help("Output style")
                 .takes_value(true),
         )
@@ -156,6 +242,32 @@ fn get_arg_matches() -> ArgMatches<'static> {
                 .possible_values(&["word", "char"])
                 .takes_value(true),
         )
+        .arg(
+            Arg::with_name("diffAlgorithm")
+                .long("diffAlgorithm")
+                .value_name("ALGORITHM")
+                .help("Algorithm used to find the changes within lines")
+                .possible_values(&["myers", "patience", "histogram", "lcs"])
+                .takes_value(true),
+        )
+        .arg(
+            Arg::with_name("ignoreWhitespace")
+                .long("ignoreWhitespace")
+                .value_name("MODE")
+                .help("Whitespace changes shown as ignored within lines")
+                .possible_values(&["none", "all", "change", "eol"])
+                .takes_value(true),
+        )
+        .arg(
+            Arg::with_name("ignoreCase")
+                .long("ignoreCase")
+                .help("Show case changes as ignored within lines"),
+        )
+        .arg(
+            Arg::with_name("hideWhitespaceChanges")
+                .long("hideWhitespaceChanges")
+                .help("Hide changes that only touch whitespace"),
+        )
         .arg(
             Arg::with_name("input")
                 .long("input")
@@ -179,6 +291,25 @@ fn get_arg_matches() -> ArgMatches<'static> {
                 .help("Send output to file (overrides output option)")
                 .takes_value(true),
         )
+        .arg(
+            Arg::with_name("srcPrefix")
+                .long("srcPrefix")
+                .value_name("PREFIX")
+                .help("Prefix of the source file names, detected from the diff by default")
+                .takes_value(true),
+        )
+        .arg(
+            Arg::with_name("dstPrefix")
+                .long("dstPrefix")
+                .value_name("PREFIX")
+                .help("Prefix of the destination file names, detected from the diff by default")
+                .takes_value(true),
+        )
+        .arg(
+            Arg::with_name("noPrefix")
+                .long("noPrefix")
+                .help("File names in the diff have no source or destination prefix"),
+        )
         .arg(
             Arg::with_name("ignore")
                 .long("ignore")
@@ -195,7 +326,7 @@ fn get_arg_matches() -> ArgMatches<'static> {
         .get_matches()
 }
 
-fn get_git_diff(config: &Diff2HtmlConfig) -> Vec<u8> {
+fn get_git_diff(config: &Diff2HtmlConfig) -> io::Result<Child> {
     let mut args: Vec<String> = match &config.trail {
         Some(trailing) => trailing.to_owned(),
         _ => vec!["-M", "-C", "HEAD"]
@@ -204,15 +335,30 @@ fn get_git_diff(config: &Diff2HtmlConfig) -> Vec<u8> {
             .collect(),
     };
 
+    // `diff2html -- log -p ...` runs `git log` instead of `git diff`.
+    let command = match args.first().map(|v| v.as_str()) {
+        Some("diff") | Some("log") | Some("show") => args.remove(0),
+        _ => "diff".to_owned(),
+    };
+
     if !args.contains(&"--no-color".to_owned()) {
         args.push("--no-color".to_owned());
     }
 
     Command::new("git")
         .arg("--no-pager")
-        .arg("diff")
+        .arg(command)
         .args(args)
-        .output()
-        .unwrap()
-        .stdout
+        .stdout(Stdio::piped())
+        .spawn()
+        .map_err(|error| io::Error::new(error.kind(), format!("could not run git: {}", error)))
+}
+
+fn wait_for_git(mut git: Child) -> io::Result<()> {
+    let status = git.wait()?;
+    if status.success() {
+        Ok(())
+    } else {
+        Err(io::Error::other(format!("git failed with {}", status)))
+    }
 }
@@ -0,0 +1,278 @@
+/*
+ * Decoding and encoding of `GIT binary patch` payloads: every line is base85 encoded
+ * zlib data, prefixed with a character giving the decoded length of the line.
+ */
+
+static BASE85: &[u8] =
+    b"0123456789ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz!#$%&()*+-;<=>?@^_`{|}~";
+
+/// Decodes one data line of a binary patch.
+pub fn decode_base85_line(line: &str) -> Option<Vec<u8>> {
+    let bytes = line.as_bytes();
+    let length = match bytes.first()? {
+        c @ b'A'..=b'Z' => (c - b'A' + 1) as usize,
+        c @ b'a'..=b'z' => (c - b'a' + 27) as usize,
+        _ => return None,
+    };
+
+    let encoded = &bytes[1..];
+    if encoded.len() != length.div_ceil(4) * 5 {
+        return None;
+    }
+
+    let mut decoded = Vec::with_capacity(encoded.len() / 5 * 4);
+    for group in encoded.chunks(5) {
+        let mut value: u64 = 0;
+        for c in group {
+            let digit = BASE85.iter().position(|v| v == c)?;
+            value = value * 85 + digit as u64;
+        }
+        if value > u32::MAX as u64 {
+            return None;
+        }
+        decoded.extend_from_slice(&(value as u32).to_be_bytes());
+    }
+
+    decoded.truncate(length);
+    Some(decoded)
+}
+
+/// Encodes 1 to 52 bytes as one data line of a binary patch.
+///
+/// Panics on any other length, which the length character cannot express.
+pub fn encode_base85_line(data: &[u8]) -> String {
+    assert!(
+        !data.is_empty() && data.len() <= 52,
+        "a binary patch line holds 1 to 52 bytes, not {}",
+        data.len()
+    );
+
+    let mut line = String::with_capacity(1 + data.len().div_ceil(4) * 5);
+    line.push(match data.len() {
+        length @ 1..=26 => (b'A' + length as u8 - 1) as char,
+        length => (b'a' + length as u8 - 27) as char,
+    });
+
+    for group in data.chunks(4) {
+        let mut bytes = [0u8; 4];
+        bytes[..group.len()].copy_from_slice(group);
+        let mut value = u32::from_be_bytes(bytes);
+
+        let mut digits = [0u8; 5];
+        for digit in digits.iter_mut().rev() {
+            *digit = BASE85[(value % 85) as usize];
+            value /= 85;
+        }
+        line.extend(digits.iter().map(|digit| *digit as char));
+    }
+
+    line
+}
+
+/// Compresses data into a zlib stream.
+pub fn deflate(data: &[u8]) -> Vec<u8> {
+    miniz_oxide::deflate::compress_to_vec_zlib(data, 6)
+}
+
+/// Inflates a zlib stream, failing when it holds more than `limit` bytes so
+/// a small hunk cannot claim any amount of memory.
+pub fn inflate(data: &[u8], limit: usize) -> Option<Vec<u8>> {
+    miniz_oxide::inflate::decompress_to_vec_zlib_with_limit(data, limit).ok()
+}
+
+/// Reads the source and target sizes from the header of a delta.
+pub fn delta_sizes(delta: &[u8]) -> Option<(usize, usize)> {
+    let mut pos = 0;
+    let source = read_varint(delta, &mut pos)?;
+    let target = read_varint(delta, &mut pos)?;
+    Some((source, target))
+}
+
+/// Applies a git delta to `base`.
+pub fn apply_delta(base: &[u8], delta: &[u8]) -> Option<Vec<u8>> {
+    let mut pos = 0;
+    let source_size = read_varint(delta, &mut pos)?;
+    let target_size = read_varint(delta, &mut pos)?;
+    if source_size != base.len() {
+        return None;
+    }
+
+    // The sizes come from the input, they are only trusted once checked.
+    let mut out = Vec::with_capacity(target_size.min(base.len() + delta.len()));
+    while pos < delta.len() && out.len() <= target_size {
+        let command = delta[pos];
+        pos += 1;
+
+        if command & 0x80 != 0 {
+            // Copy from the base; the low bits say which offset and size bytes follow.
+            let mut offset = 0usize;
+            let mut size = 0usize;
+            for i in 0..4 {
+                if command & (1 << i) != 0 {
+                    offset |= (*delta.get(pos)? as usize) << (8 * i);
+                    pos += 1;
+                }
+            }
+            for i in 0..3 {
+                if command & (0x10 << i) != 0 {
+                    size |= (*delta.get(pos)? as usize) << (8 * i);
+                    pos += 1;
+                }
+            }
+            if size == 0 {
+                size = 0x10000;
+            }
+            out.extend_from_slice(base.get(offset..offset.checked_add(size)?)?);
+        } else if command != 0 {
+            let size = command as usize;
+            out.extend_from_slice(delta.get(pos..pos + size)?);
+            pos += size;
+        } else {
+            return None;
+        }
+    }
+
+    if out.len() != target_size {
+        return None;
+    }
+
+    Some(out)
+}
+
+fn read_varint(data: &[u8], pos: &mut usize) -> Option<usize> {
+    let mut value = 0usize;
+    let mut shift = 0;
+    loop {
+        let byte = *data.get(*pos)?;
+        *pos += 1;
+        value |= ((byte & 0x7f) as usize).checked_shl(shift)?;
+        shift += 7;
+        if byte & 0x80 == 0 {
+            return Some(value);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parse::{parse_diff, try_parse_diff, BinaryPatchKind, DiagnosticKind};
+
+    // `git diff --binary` after changing four bytes of `big.bin` and a word of
+    // `small.bin`.
+    static PATCH: &str = "diff --git a/big.bin b/big.bin
+index 7e5818891cf4ff710f8ef8ff1d16dadd593935dd..25f20fa4a2b7fe9da9752c65a6a7eac1b654e88c 100644
+GIT binary patch
+delta 17
+ZcmdlXzC(P&3uYEaCuf(<ADPau0{}jX2EhOT
+
+delta 30
+ccmdlXzC(P&3+Bz|IbJeNe!$Fu%y`EL0NP0nnE(I)
+
+diff --git a/small.bin b/small.bin
+index 8e5da76b24d9a89e507982011ad21f4c191580f6..2afccb21aef30707056a527abacdbdbacd9c461d 100644
+GIT binary patch
+literal 12
+Tcmc~u&B@7UD9K1IO639o9X$j$
+
+literal 12
+Tcmc~u&B@7UD9<m-N#Ozj9g+k`
+
+";
+
+    fn old_big() -> Vec<u8> {
+        (0..3000).map(|i| (i * 7 % 251) as u8).collect()
+    }
+
+    fn new_big() -> Vec<u8> {
+        let mut data = old_big();
+        data[1000..1004].copy_from_slice(b"ABCD");
+        data
+    }
+
+    #[test]
+    fn decodes_literal_hunks() {
+        let files = parse_diff(PATCH);
+        let patch = files[1].binary_patch.as_ref().unwrap();
+
+        assert_eq!(patch.forward.kind, BinaryPatchKind::Literal);
+        assert_eq!(patch.forward.data, b"hello\0there\n");
+        assert_eq!(patch.reverse.as_ref().unwrap().data, b"hello\0world\n");
+        assert_eq!(files[1].old_size, Some(12));
+        assert_eq!(files[1].new_size, Some(12));
+    }
+
+    #[test]
+    fn decodes_and_applies_delta_hunks() {
+        let files = parse_diff(PATCH);
+        let patch = files[0].binary_patch.as_ref().unwrap();
+
+        assert_eq!(patch.forward.kind, BinaryPatchKind::Delta);
+        assert_eq!(patch.forward.size, 17);
+        assert_eq!(delta_sizes(&patch.forward.data), Some((3000, 3000)));
+        assert_eq!(patch.apply(&old_big()), Some(new_big()));
+        assert_eq!(patch.revert(&new_big()), Some(old_big()));
+        // A delta only applies to the base it was made from.
+        assert_eq!(patch.apply(&new_big()[..2999]), None);
+    }
+
+    #[test]
+    fn inflate_stops_at_the_limit() {
+        let line = decode_base85_line("Tcmc~u&B@7UD9K1IO639o9X$j$").unwrap();
+        assert_eq!(inflate(&line, 12), Some(b"hello\0there\n".to_vec()));
+        assert_eq!(inflate(&line, 11), None);
+
+        let bomb = deflate(&vec![0; 1 << 20]);
+        assert!(bomb.len() < 2048);
+        assert_eq!(inflate(&bomb, 4096), None);
+    }
+
+    #[test]
+    fn hunks_larger_than_declared_are_rejected() {
+        let patch = PATCH.replace("literal 12\nTcmc~u&B@7UD9<m", "literal 2\nTcmc~u&B@7UD9<m");
+        let diagnostics = try_parse_diff(&patch).unwrap_err();
+        assert_eq!(diagnostics.len(), 1);
+        assert_eq!(diagnostics[0].kind, DiagnosticKind::InvalidBinaryPatch);
+        assert_eq!(diagnostics[0].line, 18);
+
+        let files = parse_diff(&patch);
+        assert!(files[1].binary_patch.as_ref().unwrap().reverse.is_none());
+    }
+
+    #[test]
+    fn base85_lines_round_trip() {
+        let data = (0..=255).collect::<Vec<u8>>();
+        for length in 1..=52 {
+            let line = encode_base85_line(&data[..length]);
+            assert_eq!(decode_base85_line(&line), Some(data[..length].to_vec()));
+        }
+        assert_eq!(decode_base85_line("B0000"), None);
+        assert_eq!(decode_base85_line("0"), None);
+    }
+
+    #[test]
+    #[should_panic]
+    fn base85_lines_hold_at_least_one_byte() {
+        encode_base85_line(&[]);
+    }
+
+    #[test]
+    #[should_panic]
+    fn base85_lines_hold_at_most_52_bytes() {
+        encode_base85_line(&[0; 53]);
+    }
+
+    #[test]
+    fn deflate_round_trips() {
+        let data = new_big();
+        assert_eq!(inflate(&deflate(&data), data.len()), Some(data));
+        assert_eq!(inflate(&deflate(&[]), 0), Some(Vec::new()));
+    }
+
+    #[test]
+    fn delta_sizes_are_not_trusted() {
+        // Claims a huge result, then copies nothing.
+        let delta = [0, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0x7f, 1, b'x'];
+        assert_eq!(apply_delta(&[], &delta), None);
+    }
+}
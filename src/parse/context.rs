@@ -0,0 +1,254 @@
+/*
+ * Hunks of context diffs (`diff -c`) list the old and the new lines in two
+ * separate sections, marking changed lines with "!" on both sides. A section
+ * without changes is left out entirely.
+ */
+
+pub struct ContextHunk {
+    // Input line the hunk starts at.
+    pub line_number: usize,
+    pub old_range: Option<(usize, Option<usize>)>,
+    pub new_range: Option<(usize, Option<usize>)>,
+    pub old_lines: Vec<String>,
+    pub new_lines: Vec<String>,
+}
+
+impl ContextHunk {
+    pub fn new(line_number: usize) -> ContextHunk {
+        ContextHunk {
+            line_number,
+            old_range: None,
+            new_range: None,
+            old_lines: Vec::new(),
+            new_lines: Vec::new(),
+        }
+    }
+
+    pub fn push_line(&mut self, line: &str) {
+        if self.new_range.is_some() {
+            self.new_lines.push(line.to_owned());
+        } else {
+            self.old_lines.push(line.to_owned());
+        }
+    }
+
+    /// Whether both sections list the same unchanged lines, which the
+    /// conversion pairs up in order. Truncated hunks do not.
+    pub fn sections_match(&self) -> bool {
+        let context = |lines: &[String]| lines.iter().filter(|line| line.starts_with("  ")).count();
+        self.old_lines.is_empty()
+            || self.new_lines.is_empty()
+            || context(&self.old_lines) == context(&self.new_lines)
+    }
+
+    /// Converts the hunk to a unified hunk header and its lines.
+    pub fn to_unified(&self) -> (String, Vec<String>) {
+        let old_lines = section_lines(&self.old_lines, &self.new_lines, '-');
+        let new_lines = section_lines(&self.new_lines, &self.old_lines, '+');
+
+        let mut lines = Vec::new();
+        let (mut i, mut j) = (0, 0);
+        while i < old_lines.len() || j < new_lines.len() {
+            let old = old_lines.get(i);
+            let new = new_lines.get(j);
+
+            if old.is_some_and(|v| v.starts_with('-')) {
+                i = push_line(&mut lines, &old_lines, i, '-');
+            } else if new.is_some_and(|v| v.starts_with('+')) {
+                j = push_line(&mut lines, &new_lines, j, '+');
+            } else if old.is_some_and(|v| v.starts_with('!'))
+                || new.is_some_and(|v| v.starts_with('!'))
+            {
+                while old_lines.get(i).is_some_and(|v| v.starts_with('!')) {
+                    i = push_line(&mut lines, &old_lines, i, '-');
+                }
+                while new_lines.get(j).is_some_and(|v| v.starts_with('!')) {
+                    j = push_line(&mut lines, &new_lines, j, '+');
+                }
+            } else if new.is_some() {
+                i = skip_line(&old_lines, i);
+                j = push_line(&mut lines, &new_lines, j, ' ');
+            } else {
+                // The new section ran out of context, see `sections_match`.
+                i = push_line(&mut lines, &old_lines, i, ' ');
+            }
+        }
+
+        let (old_start, old_count) = unified_range(self.old_range, &lines, '+');
+        let (new_start, new_count) = unified_range(self.new_range, &lines, '-');
+        let header = format!(
+            "@@ -{},{} +{},{} @@",
+            old_start, old_count, new_start, new_count
+        );
+
+        (header, lines)
+    }
+}
+
+// Lines of one section as "marker + content", rebuilt from the other
+// section's context when it was left out.
+fn section_lines(lines: &[String], other: &[String], marker: char) -> Vec<String> {
+    if lines.is_empty() {
+        return other
+            .iter()
+            .filter(|line| line.starts_with(' '))
+            .map(|line| format!(" {}", line.get(2..).unwrap_or("")))
+            .collect();
+    }
+
+    lines
+        .iter()
+        .map(|line| {
+            if line.starts_with('\\') {
+                line.to_owned()
+            } else {
+                let prefix = match line.chars().next() {
+                    Some('!') => '!',
+                    Some(c) if c == marker => marker,
+                    _ => ' ',
+                };
+                format!("{}{}", prefix, line.get(2..).unwrap_or(""))
+            }
+        })
+        .collect()
+}
+
+// Pushes a line with the given marker, followed by its "\ No newline at end
+// of file" line if it has one.
+fn push_line(out: &mut Vec<String>, lines: &[String], index: usize, marker: char) -> usize {
+    let line = &lines[index];
+    out.push(format!("{}{}", marker, line.get(1..).unwrap_or("")));
+    if let Some(next) = lines.get(index + 1).filter(|v| v.starts_with('\\')) {
+        out.push(next.to_owned());
+    }
+    skip_line(lines, index)
+}
+
+fn skip_line(lines: &[String], index: usize) -> usize {
+    if lines.get(index + 1).is_some_and(|v| v.starts_with('\\')) {
+        index + 2
+    } else {
+        index + 1
+    }
+}
+
+// A single number is either a one line range or an empty range after it.
+fn unified_range(
+    range: Option<(usize, Option<usize>)>,
+    lines: &[String],
+    other_marker: char,
+) -> (usize, usize) {
+    let count = lines
+        .iter()
+        .filter(|line| !line.starts_with(other_marker) && !line.starts_with('\\'))
+        .count();
+
+    match range {
+        Some((start, Some(end))) => (start, (end + 1).saturating_sub(start)),
+        Some((start, None)) if count == 0 => (start, 0),
+        Some((start, None)) => (start, 1),
+        None => (0, count),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::parse::{parse_diff, try_parse_diff, DiagnosticKind, LineType};
+
+    fn contents(input: &str) -> Vec<String> {
+        parse_diff(input)[0].blocks[0]
+            .lines
+            .iter()
+            .map(|line| line.content.to_owned())
+            .collect()
+    }
+
+    #[test]
+    fn converts_changed_and_added_lines() {
+        let input = "*** c1
+--- c2
+***************
+*** 1,4 ****
+  a
+! b
+  c
+  d
+--- 1,5 ----
+  a
+! B
+  c
+  d
++ e
+";
+        let files = parse_diff(input);
+        assert_eq!(files[0].old_name.as_deref(), Some("c1"));
+        assert_eq!(files[0].new_name.as_deref(), Some("c2"));
+        assert_eq!(
+            files[0].blocks[0].header.as_deref(),
+            Some("@@ -1,4 +1,5 @@")
+        );
+        assert_eq!(contents(input), vec![" a", "-b", "+B", " c", " d", "+e"]);
+    }
+
+    #[test]
+    fn rebuilds_a_left_out_section() {
+        let input = "*** c3
+--- c4
+***************
+*** 1,4 ****
+  a
+- b
+  c
+  d
+--- 1,3 ----
+";
+        let files = parse_diff(input);
+        let block = &files[0].blocks[0];
+        assert_eq!(block.header.as_deref(), Some("@@ -1,4 +1,3 @@"));
+        assert_eq!(contents(input), vec![" a", "-b", " c", " d"]);
+        assert_eq!(block.lines[2].old_number, Some(3));
+        assert_eq!(block.lines[2].new_number, Some(2));
+    }
+
+    #[test]
+    fn keeps_no_newline_markers() {
+        let input = "*** c5
+--- c6
+***************
+*** 1,2 ****
+  x
+! y
+\\ No newline at end of file
+--- 1,2 ----
+  x
+! z
+\\ No newline at end of file
+";
+        let files = parse_diff(input);
+        let lines = &files[0].blocks[0].lines;
+        assert_eq!(contents(input), vec![" x", "-y", "+z"]);
+        assert_eq!(lines[1].line_type, Some(LineType::Deletes));
+        assert!(lines[1].no_newline_at_eof);
+        assert!(lines[2].no_newline_at_eof);
+    }
+
+    #[test]
+    fn reports_sections_that_do_not_match() {
+        let input = "*** c1
+--- c2
+***************
+*** 1,4 ****
+  a
+! b
+  c
+  d
+--- 1,2 ----
+  a
+! B
+";
+        let diagnostics = try_parse_diff(input).unwrap_err();
+        assert_eq!(diagnostics[0].kind, DiagnosticKind::HunkLengthMismatch);
+        assert_eq!(diagnostics[0].line, 4);
+        assert_eq!(contents(input), vec![" a", "-b", "+B", " c", " d"]);
+    }
+}
@@ -0,0 +1,315 @@
+/*
+ * Builds a file from two versions of a text, so comparisons of arbitrary
+ * strings can be rendered without running `diff` first. Lines are compared
+ * with a line `Changeset` of the configured algorithm and grouped into blocks the way `diff -u` does.
+ */
+
+use std::collections::HashMap;
+
+use difference::Difference;
+
+use super::{Block, File, LanguageRegistry, Line, LineType};
+use crate::config::Diff2HtmlConfig;
+use crate::difference::{Algorithm, Changeset, SplitType};
+use crate::patch;
+
+#[derive(Clone, Debug)]
+pub struct GenerateOptions {
+    // Lines of context around every change. Changes closer than twice this
+    // share a block.
+    pub context_lines: usize,
+    pub algorithm: Algorithm,
+    // Languages the file names are looked up in, like
+    // `LanguageRegistry::from_config` for the configured ones.
+    pub languages: LanguageRegistry,
+}
+
+impl Default for GenerateOptions {
+    fn default() -> GenerateOptions {
+        GenerateOptions {
+            context_lines: 3,
+            algorithm: Algorithm::from_name(&Diff2HtmlConfig::default().diff_algorithm),
+            languages: LanguageRegistry::default(),
+        }
+    }
+}
+
+/// Compares `old` and `new`, named `old_name` and `new_name`.
+pub fn generate_diff(
+    old_name: &str,
+    old: &str,
+    new_name: &str,
+    new: &str,
+    options: &GenerateOptions,
+) -> File {
+    let mut file = File::new();
+    file.old_name = Some(old_name.to_owned());
+    file.new_name = Some(new_name.to_owned());
+    file.language = [new_name, old_name]
+        .iter()
+        .filter(|name| **name != "/dev/null")
+        .find_map(|name| options.languages.detect(name));
+
+    // Last line of each side when it has no newline at its end.
+    let old_last = Some(split_lines(old).len()).filter(|_| !old.ends_with('\n'));
+    let new_last = Some(split_lines(new).len()).filter(|_| !new.ends_with('\n'));
+
+    let mut old_number = 1;
+    let mut new_number = 1;
+    let mut lines = Vec::new();
+    for (line_type, content) in get_changes(old, new, &options.algorithm) {
+        let is_old_last = old_last == Some(old_number);
+        let is_new_last = new_last == Some(new_number);
+        // A kept line that only lost or gained its newline changed as well.
+        let is_changed = line_type == LineType::Context && is_old_last != is_new_last;
+
+        if line_type == LineType::Deletes || is_changed {
+            let mut line = Line::new(format!("-{}", content));
+            line.line_type = Some(LineType::Deletes);
+            line.old_number = Some(old_number);
+            line.no_newline_at_eof = is_old_last;
+            lines.push(line);
+            old_number += 1;
+            file.deleted_lines += 1;
+        }
+        if line_type == LineType::Inserts || is_changed {
+            let mut line = Line::new(format!("+{}", content));
+            line.line_type = Some(LineType::Inserts);
+            line.new_number = Some(new_number);
+            line.no_newline_at_eof = is_new_last;
+            lines.push(line);
+            new_number += 1;
+            file.added_lines += 1;
+        }
+        if line_type == LineType::Context && !is_changed {
+            let mut line = Line::new(format!(" {}", content));
+            line.line_type = Some(LineType::Context);
+            line.old_number = Some(old_number);
+            line.new_number = Some(new_number);
+            line.no_newline_at_eof = is_old_last;
+            lines.push(line);
+            old_number += 1;
+            new_number += 1;
+        }
+    }
+
+    file.blocks = get_blocks(lines, options.context_lines);
+    file
+}
+
+// Every line of both texts, marked as kept, removed or added. A line
+// `Changeset` joins the lines of a chunk without a separator, so lines are
+// compared as keys of the same width that split back into lines.
+fn get_changes<'a>(old: &'a str, new: &'a str, algorithm: &Algorithm) -> Vec<(LineType, &'a str)> {
+    let old_lines = split_lines(old);
+    let new_lines = split_lines(new);
+
+    let mut keys = HashMap::new();
+    let mut distinct = Vec::new();
+    for line in old_lines.iter().chain(new_lines.iter()) {
+        keys.entry(*line).or_insert_with(|| {
+            distinct.push(*line);
+            distinct.len() - 1
+        });
+    }
+    let width = format!("{:x}", distinct.len()).len();
+    let to_keys = |lines: &[&str]| {
+        lines
+            .iter()
+            .map(|line| format!("{:0width$x}", keys[line], width = width))
+            .collect::<Vec<String>>()
+            .join("\n")
+    };
+
+    let changeset = Changeset::with_algorithm(
+        &to_keys(&old_lines),
+        &to_keys(&new_lines),
+        &SplitType::Line,
+        algorithm,
+    );
+    let mut changes = Vec::new();
+    for diff in changeset.diffs.iter() {
+        let (line_type, chunk) = match diff {
+            Difference::Same(chunk) => (LineType::Context, chunk),
+            Difference::Rem(chunk) => (LineType::Deletes, chunk),
+            Difference::Add(chunk) => (LineType::Inserts, chunk),
+        };
+        for start in (0..chunk.len()).step_by(width) {
+            let key = usize::from_str_radix(&chunk[start..start + width], 16).unwrap();
+            changes.push((line_type.clone(), distinct[key]));
+        }
+    }
+
+    changes
+}
+
+fn split_lines(text: &str) -> Vec<&str> {
+    let mut lines = text.split('\n').collect::<Vec<&str>>();
+    if text.is_empty() || text.ends_with('\n') {
+        lines.pop();
+    }
+    lines
+}
+
+// Groups the lines into blocks of changes with their context.
+fn get_blocks(lines: Vec<Line>, context_lines: usize) -> Vec<Block> {
+    let changes = lines
+        .iter()
+        .enumerate()
+        .filter(|(_, line)| line.line_type != Some(LineType::Context))
+        .map(|(i, _)| i)
+        .collect::<Vec<usize>>();
+
+    // Start and end of every block in the lines.
+    let mut ranges: Vec<(usize, usize)> = Vec::new();
+    for i in changes {
+        let start = i.saturating_sub(context_lines);
+        let end = (i + 1 + context_lines).min(lines.len());
+        match ranges.last_mut() {
+            Some(range) if start <= range.1 => range.1 = end,
+            _ => ranges.push((start, end)),
+        }
+    }
+
+    let mut offset = 0;
+    ranges
+        .into_iter()
+        .map(|(start, end)| {
+            let mut block = Block::new();
+            block.lines = lines[start..end].to_vec();
+
+            // An empty range starts at the line before it.
+            let old_before = lines[..start]
+                .iter()
+                .filter(|line| line.line_type != Some(LineType::Inserts))
+                .count();
+            let has_old = block
+                .lines
+                .iter()
+                .any(|line| line.line_type != Some(LineType::Inserts));
+            block.old_start = Some(old_before + if has_old { 1 } else { 0 });
+
+            let ranges = patch::get_block_ranges(&block, &mut offset);
+            patch::set_ranges(&mut block, ranges);
+            block
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::Diff2HtmlConfig;
+
+    #[test]
+    fn detects_languages_from_the_registry() {
+        let mut config = Diff2HtmlConfig::default();
+        config
+            .language_extensions
+            .insert("tpl".to_owned(), "handlebars".to_owned());
+        let options = GenerateOptions {
+            languages: LanguageRegistry::from_config(&config),
+            ..GenerateOptions::default()
+        };
+
+        let file = generate_diff("a.tpl", "a\n", "a.tpl", "b\n", &options);
+        assert_eq!(file.language.as_deref(), Some("handlebars"));
+        let file = generate_diff("a.tpl", "a\n", "a.tpl", "b\n", &GenerateOptions::default());
+        assert_eq!(file.language, None);
+    }
+
+    fn lines(block: &Block) -> Vec<&str> {
+        block
+            .lines
+            .iter()
+            .map(|line| line.content.as_str())
+            .collect()
+    }
+
+    fn headers(file: &File) -> Vec<&str> {
+        file.blocks
+            .iter()
+            .filter_map(|block| block.header.as_deref())
+            .collect()
+    }
+
+    #[test]
+    fn groups_changes_with_their_context() {
+        let old = "1\n2\n3\n4\n5\n6\n7\n8\n9\n10\n11\n12\n";
+        let new = "1\nTWO\n3\n4\n5\n6\n7\n8\n9\n10\nELEVEN\n12\n";
+        let file = generate_diff("x", old, "x", new, &GenerateOptions::default());
+        assert_eq!(headers(&file), vec!["@@ -1,5 +1,5 @@", "@@ -8,5 +8,5 @@"]);
+        assert_eq!(
+            lines(&file.blocks[0]),
+            vec![" 1", "-2", "+TWO", " 3", " 4", " 5"]
+        );
+
+        let options = GenerateOptions {
+            context_lines: 5,
+            ..GenerateOptions::default()
+        };
+        let file = generate_diff("x", old, "x", new, &options);
+        assert_eq!(headers(&file), vec!["@@ -1,12 +1,12 @@"]);
+        assert_eq!(file.added_lines, 2);
+        assert_eq!(file.deleted_lines, 2);
+    }
+
+    #[test]
+    fn moves_later_blocks_by_the_lines_before_them() {
+        let old = "1\n2\n3\n4\n5\n6\n7\n8\n9\n10\n11\n12\n";
+        let new = "1\n3\n4\n5\n6\n7\n8\n9\n10\nELEVEN\n12\n";
+        let file = generate_diff("x", old, "x", new, &GenerateOptions::default());
+        assert_eq!(headers(&file), vec!["@@ -1,5 +1,4 @@", "@@ -8,5 +7,5 @@"]);
+        assert_eq!(file.blocks[1].new_start, Some(7));
+        assert_eq!(file.blocks[1].new_count, Some(5));
+    }
+
+    #[test]
+    fn keeps_empty_lines() {
+        let file = generate_diff("x", "\n\na\n", "x", "\nb\n\n", &GenerateOptions::default());
+        assert_eq!(lines(&file.blocks[0]), vec![" ", "+b", " ", "-a"]);
+
+        let file = generate_diff("x", "", "x", "\n", &GenerateOptions::default());
+        assert_eq!(headers(&file), vec!["@@ -0,0 +1 @@"]);
+        assert_eq!(lines(&file.blocks[0]), vec!["+"]);
+    }
+
+    #[test]
+    fn marks_lines_without_a_newline() {
+        let file = generate_diff("x", "a\nb", "x", "a\nb\n", &GenerateOptions::default());
+        assert_eq!(lines(&file.blocks[0]), vec![" a", "-b", "+b"]);
+        assert!(file.blocks[0].lines[1].no_newline_at_eof);
+        assert!(!file.blocks[0].lines[2].no_newline_at_eof);
+    }
+
+    #[test]
+    fn compares_lines_with_every_algorithm() {
+        let old = "a\nb\nc\na\nb\nb\na\n";
+        let new = "c\nb\na\nb\na\nc\n";
+        for algorithm in [
+            Algorithm::Lcs,
+            Algorithm::Myers,
+            Algorithm::Patience,
+            Algorithm::Histogram,
+        ] {
+            let options = GenerateOptions {
+                algorithm,
+                ..GenerateOptions::default()
+            };
+            let file = generate_diff("x", old, "x", new, &options);
+            let (mut old_text, mut new_text) = (String::new(), String::new());
+            for line in file.blocks.iter().flat_map(|block| block.lines.iter()) {
+                if line.line_type != Some(LineType::Inserts) {
+                    old_text += &line.content[1..];
+                    old_text.push('\n');
+                }
+                if line.line_type != Some(LineType::Deletes) {
+                    new_text += &line.content[1..];
+                    new_text.push('\n');
+                }
+            }
+            assert_eq!((old_text.as_str(), new_text.as_str()), (old, new));
+            assert_eq!(file.deleted_lines, file.added_lines + 1);
+        }
+    }
+}
@@ -0,0 +1,194 @@
+/*
+ * Maps file names to canonical language names. Exact file names like
+ * `Makefile` win over extensions, and longer extensions like `d.ts` win over
+ * shorter ones. Scripts without either are recognized by their shebang line.
+ */
+
+use std::collections::HashMap;
+
+use crate::config::Diff2HtmlConfig;
+
+static EXTENSIONS: &[(&str, &str)] = &[
+    ("bash", "bash"),
+    ("c", "c"),
+    ("cc", "cpp"),
+    ("clj", "clojure"),
+    ("cljs", "clojure"),
+    ("cmake", "cmake"),
+    ("coffee", "coffeescript"),
+    ("cpp", "cpp"),
+    ("cs", "csharp"),
+    ("css", "css"),
+    ("cxx", "cpp"),
+    ("d.ts", "typescript"),
+    ("dart", "dart"),
+    ("diff", "diff"),
+    ("elm", "elm"),
+    ("erl", "erlang"),
+    ("ex", "elixir"),
+    ("exs", "elixir"),
+    ("fs", "fsharp"),
+    ("go", "go"),
+    ("gradle", "groovy"),
+    ("graphql", "graphql"),
+    ("groovy", "groovy"),
+    ("h", "c"),
+    ("hh", "cpp"),
+    ("hpp", "cpp"),
+    ("hs", "haskell"),
+    ("htm", "html"),
+    ("html", "html"),
+    ("ini", "ini"),
+    ("java", "java"),
+    ("js", "javascript"),
+    ("json", "json"),
+    ("jsx", "javascript"),
+    ("kt", "kotlin"),
+    ("kts", "kotlin"),
+    ("less", "less"),
+    ("lua", "lua"),
+    ("m", "objectivec"),
+    ("md", "markdown"),
+    ("markdown", "markdown"),
+    ("mjs", "javascript"),
+    ("ml", "ocaml"),
+    ("mm", "objectivec"),
+    ("patch", "diff"),
+    ("php", "php"),
+    ("pl", "perl"),
+    ("pm", "perl"),
+    ("proto", "protobuf"),
+    ("ps1", "powershell"),
+    ("py", "python"),
+    ("r", "r"),
+    ("rb", "ruby"),
+    ("rs", "rust"),
+    ("sass", "scss"),
+    ("scala", "scala"),
+    ("scss", "scss"),
+    ("sh", "bash"),
+    ("sql", "sql"),
+    ("svelte", "svelte"),
+    ("swift", "swift"),
+    ("tex", "latex"),
+    ("toml", "toml"),
+    ("ts", "typescript"),
+    ("tsx", "typescript"),
+    ("txt", "plaintext"),
+    ("vue", "vue"),
+    ("xml", "xml"),
+    ("yaml", "yaml"),
+    ("yml", "yaml"),
+    ("zsh", "bash"),
+];
+
+static FILENAMES: &[(&str, &str)] = &[
+    (".bash_profile", "bash"),
+    (".bashrc", "bash"),
+    (".profile", "bash"),
+    (".zshrc", "bash"),
+    ("CMakeLists.txt", "cmake"),
+    ("Cargo.lock", "toml"),
+    ("Containerfile", "dockerfile"),
+    ("Dockerfile", "dockerfile"),
+    ("GNUmakefile", "makefile"),
+    ("Gemfile", "ruby"),
+    ("Jenkinsfile", "groovy"),
+    ("Makefile", "makefile"),
+    ("Rakefile", "ruby"),
+    ("Vagrantfile", "ruby"),
+    ("makefile", "makefile"),
+];
+
+static INTERPRETERS: &[(&str, &str)] = &[
+    ("bash", "bash"),
+    ("node", "javascript"),
+    ("perl", "perl"),
+    ("php", "php"),
+    ("python", "python"),
+    ("ruby", "ruby"),
+    ("sh", "bash"),
+    ("zsh", "bash"),
+];
+
+#[derive(Clone, Debug)]
+pub struct LanguageRegistry {
+    extensions: HashMap<String, String>,
+    filenames: HashMap<String, String>,
+    interpreters: HashMap<String, String>,
+}
+
+impl Default for LanguageRegistry {
+    fn default() -> LanguageRegistry {
+        let to_map = |entries: &[(&str, &str)]| {
+            entries
+                .iter()
+                .map(|(key, language)| (key.to_string(), language.to_string()))
+                .collect()
+        };
+
+        LanguageRegistry {
+            extensions: to_map(EXTENSIONS),
+            filenames: to_map(FILENAMES),
+            interpreters: to_map(INTERPRETERS),
+        }
+    }
+}
+
+impl LanguageRegistry {
+    /// The built-in languages extended with the ones in the config.
+    pub fn from_config(config: &Diff2HtmlConfig) -> LanguageRegistry {
+        let mut registry = LanguageRegistry::default();
+        for (extension, language) in config.language_extensions.iter() {
+            registry.extensions.insert(
+                extension.trim_start_matches('.').to_lowercase(),
+                language.to_owned(),
+            );
+        }
+        for (filename, language) in config.language_filenames.iter() {
+            registry
+                .filenames
+                .insert(filename.to_owned(), language.to_owned());
+        }
+        for (interpreter, language) in config.language_interpreters.iter() {
+            registry
+                .interpreters
+                .insert(interpreter.to_owned(), language.to_owned());
+        }
+        registry
+    }
+
+    /// Language of a file from its path.
+    pub fn detect(&self, path: &str) -> Option<String> {
+        let filename = path.rsplit('/').next().unwrap_or(path);
+        if let Some(language) = self.filenames.get(filename) {
+            return Some(language.to_owned());
+        }
+
+        // A leading dot starts the name of a hidden file, not an extension.
+        let filename = filename.to_lowercase();
+        filename
+            .char_indices()
+            .filter(|(i, c)| *c == '.' && *i > 0)
+            .filter_map(|(i, _)| self.extensions.get(&filename[i + 1..]))
+            .next()
+            .cloned()
+    }
+
+    /// Language of a script from its `#!` line.
+    pub fn detect_shebang(&self, line: &str) -> Option<String> {
+        if !line.starts_with("#!") {
+            return None;
+        }
+
+        let mut words = line[2..].split_whitespace();
+        let mut interpreter = words.next()?.rsplit('/').next()?;
+        if interpreter == "env" {
+            interpreter = words.find(|word| !word.starts_with('-'))?;
+        }
+
+        // Versioned interpreters like `python3` or `ruby2.7`.
+        let interpreter = interpreter.trim_end_matches(|c: char| c.is_ascii_digit() || c == '.');
+        self.interpreters.get(interpreter).cloned()
+    }
+}
@@ -0,0 +1,138 @@
+/*
+ * `git log -p` and `git show` output: every commit starts with a
+ * `commit <sha>` line, followed by headers, the message indented by four
+ * spaces and the diff.
+ */
+
+use regex::Regex;
+
+use super::{parse_diff_with_config, Commit};
+use crate::config::Diff2HtmlConfig;
+
+lazy_static! {
+    static ref COMMIT: Regex = Regex::new(r#"^commit ([0-9a-f]{7,40})(?: \(.*\))?$"#).unwrap();
+    static ref HEADER: Regex = Regex::new(r#"^(\w+):\s+(.*)$"#).unwrap();
+}
+
+/// Whether the input looks like `git log` or `git show` output.
+pub fn is_git_log(input: &str) -> bool {
+    input
+        .lines()
+        .find(|line| !line.is_empty())
+        .is_some_and(|line| COMMIT.is_match(line))
+}
+
+/// Splits a log into one `Commit` per `commit <sha>` line.
+pub fn parse_log(input: &str, config: &Diff2HtmlConfig) -> Vec<Commit> {
+    let mut commits: Vec<Vec<&str>> = Vec::new();
+
+    for line in input.lines() {
+        if COMMIT.is_match(line) {
+            commits.push(Vec::new());
+        }
+        if let Some(commit) = commits.last_mut() {
+            commit.push(line);
+        }
+    }
+
+    commits
+        .iter()
+        .map(|lines| parse_commit(lines, config))
+        .collect()
+}
+
+fn parse_commit(lines: &[&str], config: &Diff2HtmlConfig) -> Commit {
+    let mut commit = Commit::new();
+
+    let mut lines = lines.iter().cloned().peekable();
+    if let Some(captures) = lines.next().and_then(|line| COMMIT.captures(line)) {
+        commit.sha = Some(captures[1].to_owned());
+    }
+
+    while let Some(line) = lines.peek() {
+        let captures = match HEADER.captures(line) {
+            Some(captures) => captures,
+            _ => break,
+        };
+        match &captures[1] {
+            "Author" => commit.author = Some(captures[2].to_owned()),
+            "Date" | "AuthorDate" => commit.date = Some(captures[2].to_owned()),
+            _ => {}
+        }
+        lines.next();
+    }
+
+    let mut message = Vec::new();
+    while let Some(line) = lines.peek() {
+        if !line.is_empty() && !line.starts_with("    ") {
+            break;
+        }
+        message.push(line.get(4..).unwrap_or(""));
+        lines.next();
+    }
+
+    let mut message = message.into_iter().skip_while(|line| line.is_empty());
+    commit.subject = message.next().map(|subject| subject.to_owned());
+    let mut message = message
+        .skip_while(|line| line.is_empty())
+        .collect::<Vec<&str>>();
+    while message.last() == Some(&"") {
+        message.pop();
+    }
+    commit.message = message.join("\n");
+
+    commit.files = parse_diff_with_config(&lines.collect::<Vec<&str>>().join("\n"), config);
+
+    commit
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    static LOG: &str = "commit 1111111111111111111111111111111111111111 (HEAD -> main)
+Author: T <t@example.com>
+Date:   Sat Oct 17 04:07:25 2026 +0000
+
+    Change the file
+
+    Because.
+
+diff --git a/x b/x
+index 1111111..2222222 100644
+--- a/x
++++ b/x
+@@ -1 +1 @@
+-a
++b
+
+commit 2222222222222222222222222222222222222222
+Author: T <t@example.com>
+Date:   Fri Oct 16 04:07:25 2026 +0000
+
+    Empty
+";
+
+    #[test]
+    fn parses_commits() {
+        let commits = parse_log(LOG, &Diff2HtmlConfig::default());
+        assert_eq!(commits.len(), 2);
+        assert_eq!(commits[0].author.as_deref(), Some("T <t@example.com>"));
+        assert_eq!(commits[0].subject.as_deref(), Some("Change the file"));
+        assert_eq!(commits[0].message, "Because.");
+        assert_eq!(commits[0].files.len(), 1);
+        assert_eq!(commits[0].files[0].new_name.as_deref(), Some("x"));
+        assert_eq!(commits[1].subject.as_deref(), Some("Empty"));
+        assert!(commits[1].files.is_empty());
+    }
+
+    #[test]
+    fn parses_diffs_with_the_config() {
+        let config = Diff2HtmlConfig {
+            no_prefix: true,
+            ..Diff2HtmlConfig::default()
+        };
+        let commits = parse_log(LOG, &config);
+        assert_eq!(commits[0].files[0].new_name.as_deref(), Some("b/x"));
+    }
+}
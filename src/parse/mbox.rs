@@ -0,0 +1,227 @@
+/*
+ * `git format-patch` output: one mail per commit, separated by mbox `From `
+ * lines. Every mail has headers, the commit message, a `---` line followed by
+ * the diffstat, the diff itself and an optional `-- ` signature.
+ */
+
+use regex::Regex;
+
+use super::{parse_diff_with_config, Commit};
+use crate::config::Diff2HtmlConfig;
+
+lazy_static! {
+    static ref MBOX_FROM: Regex = Regex::new(r#"^From (\S+) "#).unwrap();
+    static ref SHA: Regex = Regex::new(r#"^[0-9a-f]{40}$"#).unwrap();
+    static ref PATCH_PREFIX: Regex = Regex::new(r#"^\[[^\]]*PATCH[^\]]*\]\s*"#).unwrap();
+    static ref ENCODED_WORD: Regex = Regex::new(r#"=\?([^?]+)\?([bBqQ])\?([^?]*)\?="#).unwrap();
+    static ref ENCODED_WORD_SPACE: Regex = Regex::new(r#"\?=\s+=\?"#).unwrap();
+}
+
+/// Whether the input looks like `git format-patch` output.
+pub fn is_patch_series(input: &str) -> bool {
+    let mut lines = input.lines();
+    lines.next().is_some_and(|line| MBOX_FROM.is_match(line))
+        && lines
+            .take_while(|line| !line.is_empty())
+            .any(|line| line.starts_with("Subject:"))
+}
+
+/// Parses a series of mails into one `Commit` per patch.
+pub fn parse_patch_series(input: &str, config: &Diff2HtmlConfig) -> Vec<Commit> {
+    let mut mails: Vec<Vec<&str>> = Vec::new();
+    let mut prev_line = "";
+
+    for line in input.lines() {
+        if (mails.is_empty() || prev_line.is_empty()) && MBOX_FROM.is_match(line) {
+            mails.push(Vec::new());
+        }
+        if let Some(mail) = mails.last_mut() {
+            mail.push(line);
+        }
+        prev_line = line;
+    }
+
+    mails.iter().map(|mail| parse_mail(mail, config)).collect()
+}
+
+fn parse_mail(lines: &[&str], config: &Diff2HtmlConfig) -> Commit {
+    let mut commit = Commit::new();
+
+    let mut lines = lines.iter().cloned().peekable();
+    if let Some(captures) = lines.next().and_then(|line| MBOX_FROM.captures(line)) {
+        commit.sha = Some(captures[1].to_owned()).filter(|sha| SHA.is_match(sha));
+    }
+
+    // Headers, with continuation lines folded into the header they belong to.
+    let mut headers: Vec<String> = Vec::new();
+    for line in lines.by_ref() {
+        if line.is_empty() {
+            break;
+        }
+        if line.starts_with(' ') || line.starts_with('\t') {
+            if let Some(header) = headers.last_mut() {
+                header.push(' ');
+                header.push_str(line.trim());
+            }
+        } else {
+            headers.push(line.to_owned());
+        }
+    }
+
+    for header in headers.iter() {
+        if let Some(author) = header.strip_prefix("From:") {
+            commit.author = Some(decode_header(author));
+        } else if let Some(date) = header.strip_prefix("Date:") {
+            commit.date = Some(date.trim().to_owned());
+        } else if let Some(subject) = header.strip_prefix("Subject:") {
+            let subject = decode_header(subject);
+            commit.subject = Some(PATCH_PREFIX.replace(&subject, "").to_string());
+        }
+    }
+
+    let mut message = Vec::new();
+    while let Some(line) = lines.peek() {
+        if *line == "---" || line.starts_with("diff ") || line.starts_with("Index: ") {
+            break;
+        }
+        message.push(*line);
+        lines.next();
+    }
+    while message.last() == Some(&"") {
+        message.pop();
+    }
+    commit.message = message.join("\n");
+
+    // Skip the diffstat.
+    while let Some(line) = lines.peek() {
+        if line.starts_with("diff ") || line.starts_with("Index: ") {
+            break;
+        }
+        lines.next();
+    }
+
+    let diff = lines
+        .take_while(|line| *line != "-- ")
+        .collect::<Vec<&str>>()
+        .join("\n");
+    commit.files = parse_diff_with_config(&diff, config);
+
+    commit
+}
+
+// Decodes RFC 2047 encoded words like `=?UTF-8?q?J=C3=B6rg?=`.
+fn decode_header(value: &str) -> String {
+    // Whitespace between two encoded words is not part of the text.
+    let value = ENCODED_WORD_SPACE.replace_all(value.trim(), "?==?");
+    ENCODED_WORD
+        .replace_all(&value, |captures: &regex::Captures| {
+            let bytes = if captures[2].eq_ignore_ascii_case("b") {
+                decode_base64(&captures[3])
+            } else {
+                decode_quoted_printable(&captures[3])
+            };
+            String::from_utf8_lossy(&bytes).to_string()
+        })
+        .to_string()
+}
+
+fn decode_quoted_printable(value: &str) -> Vec<u8> {
+    let bytes = value.as_bytes();
+    let mut decoded = Vec::new();
+    let mut i = 0;
+    while i < bytes.len() {
+        match bytes[i] {
+            b'_' => decoded.push(b' '),
+            b'=' if i + 2 < bytes.len() => {
+                let byte = std::str::from_utf8(&bytes[i + 1..i + 3])
+                    .ok()
+                    .and_then(|hex| u8::from_str_radix(hex, 16).ok());
+                match byte {
+                    Some(byte) => {
+                        decoded.push(byte);
+                        i += 2;
+                    }
+                    None => decoded.push(b'='),
+                }
+            }
+            byte => decoded.push(byte),
+        }
+        i += 1;
+    }
+    decoded
+}
+
+fn decode_base64(value: &str) -> Vec<u8> {
+    let mut decoded = Vec::new();
+    let mut buffer = 0u32;
+    let mut bits = 0;
+    for c in value.bytes() {
+        let digit = match c {
+            b'A'..=b'Z' => c - b'A',
+            b'a'..=b'z' => c - b'a' + 26,
+            b'0'..=b'9' => c - b'0' + 52,
+            b'+' => 62,
+            b'/' => 63,
+            _ => continue,
+        };
+        buffer = (buffer << 6) | digit as u32;
+        bits += 6;
+        if bits >= 8 {
+            bits -= 8;
+            decoded.push((buffer >> bits) as u8);
+            buffer &= (1 << bits) - 1;
+        }
+    }
+    decoded
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    static SERIES: &str = "From 5b84479117b80999dad4dd8ba26197a8f0101747 Mon Sep 17 00:00:00 2001
+From: =?UTF-8?q?J=C3=B6rg?= <j@example.com>
+Date: Sat, 17 Oct 2026 04:07:25 +0000
+Subject: [PATCH 1/1] Change
+ the file
+
+Message.
+---
+ x | 2 +-
+
+diff --git a/x b/x
+index 1111111..2222222 100644
+--- a/x
++++ b/x
+@@ -1 +1 @@
+-a
++b
+-- 
+2.40.0
+";
+
+    #[test]
+    fn parses_mails() {
+        let commits = parse_patch_series(SERIES, &Diff2HtmlConfig::default());
+        assert_eq!(commits.len(), 1);
+        assert_eq!(
+            commits[0].sha.as_deref(),
+            Some("5b84479117b80999dad4dd8ba26197a8f0101747")
+        );
+        assert_eq!(commits[0].author.as_deref(), Some("Jörg <j@example.com>"));
+        assert_eq!(commits[0].subject.as_deref(), Some("Change the file"));
+        assert_eq!(commits[0].message, "Message.");
+        assert_eq!(commits[0].files.len(), 1);
+        assert_eq!(commits[0].files[0].new_name.as_deref(), Some("x"));
+    }
+
+    #[test]
+    fn parses_diffs_with_the_config() {
+        let config = Diff2HtmlConfig {
+            no_prefix: true,
+            ..Diff2HtmlConfig::default()
+        };
+        let commits = parse_patch_series(SERIES, &config);
+        assert_eq!(commits[0].files[0].new_name.as_deref(), Some("b/x"));
+    }
+}
@@ -0,0 +1,2852 @@
+use std::collections::VecDeque;
+use std::io::{self, BufRead};
+
+use regex::Regex;
+
+use crate::config::Diff2HtmlConfig;
+
+pub mod binary;
+mod context;
+mod generate;
+mod language;
+mod log;
+mod mbox;
+pub(crate) mod quote;
+mod range_diff;
+mod whitespace;
+
+use self::context::ContextHunk;
+pub use self::generate::{generate_diff, GenerateOptions};
+pub use self::language::LanguageRegistry;
+pub use self::log::{is_git_log, parse_log};
+pub use self::mbox::{is_patch_series, parse_patch_series};
+pub use self::quote::{unquote_bytes, unquote_path_bytes};
+pub use self::range_diff::{is_range_diff, parse_range_diff};
+pub use self::whitespace::{hide_file_whitespace_changes, hide_whitespace_changes};
+
+static OLD_FILE_NAME_HEADER: &str = "--- ";
+static NEW_FILE_NAME_HEADER: &str = "+++ ";
+static HUNK_HEADER_PREFIX: &str = "@@";
+static NO_NEWLINE_PREFIX: &str = "\\ ";
+static CONTEXT_OLD_FILE_NAME_HEADER: &str = "*** ";
+static CONTEXT_HUNK_SEPARATOR: &str = "***************";
+static WORD_DIFF_LINE_END: &str = "~";
+
+pub fn parse_diff(diff: &str) -> Vec<File> {
+    read_diff(diff.as_bytes()).collect()
+}
+
+/// Like `parse_diff`, but strips file name prefixes as configured.
+pub fn parse_diff_with_config(diff: &str, config: &Diff2HtmlConfig) -> Vec<File> {
+    DiffReader::with_config(diff.as_bytes(), config).collect()
+}
+
+/// Like `parse_diff`, but fails with every diagnostic found when the input
+/// contains errors instead of silently doing its best.
+pub fn try_parse_diff(diff: &str) -> Result<Vec<File>, Vec<Diagnostic>> {
+    let mut reader = read_diff(diff.as_bytes());
+    let files = reader.by_ref().collect();
+    let diagnostics = reader.into_diagnostics();
+
+    if diagnostics
+        .iter()
+        .any(|diagnostic| diagnostic.severity == Severity::Error)
+    {
+        Err(diagnostics)
+    } else {
+        Ok(files)
+    }
+}
+
+/// Parses a diff incrementally from `reader`, yielding each `File` as soon as
+/// it is complete so large inputs never have to be held in memory at once.
+pub fn read_diff<R: BufRead>(reader: R) -> DiffReader<R> {
+    DiffReader::new(reader)
+}
+
+pub struct DiffReader<R: BufRead> {
+    reader: R,
+    state: ParseState,
+    // The line being parsed followed by up to two lines of lookahead.
+    lines: VecDeque<String>,
+    prev_line: Option<String>,
+    eof: bool,
+    finished: bool,
+    // Error that stopped the reading, the files after it are missing.
+    error: Option<io::Error>,
+}
+
+impl<R: BufRead> DiffReader<R> {
+    pub fn new(reader: R) -> DiffReader<R> {
+        DiffReader {
+            reader,
+            state: ParseState::new(),
+            lines: VecDeque::new(),
+            prev_line: None,
+            eof: false,
+            finished: false,
+            error: None,
+        }
+    }
+
+    pub fn with_config(reader: R, config: &Diff2HtmlConfig) -> DiffReader<R> {
+        let mut diff_reader = DiffReader::new(reader);
+        diff_reader.state.src_prefix = config.src_prefix.clone();
+        diff_reader.state.dst_prefix = config.dst_prefix.clone();
+        diff_reader.state.no_prefix = config.no_prefix;
+        diff_reader.state.languages = LanguageRegistry::from_config(config);
+        diff_reader
+    }
+
+    /// Problems found in the input read so far.
+    pub fn diagnostics(&self) -> &[Diagnostic] {
+        &self.state.diagnostics
+    }
+
+    pub fn into_diagnostics(self) -> Vec<Diagnostic> {
+        self.state.diagnostics
+    }
+
+    /// Takes the error that stopped the reading, if any. The files yielded
+    /// before it are complete, the rest of the input is missing.
+    pub fn take_error(&mut self) -> Option<io::Error> {
+        self.error.take()
+    }
+
+    fn fill(&mut self) {
+        let mut buf = Vec::new();
+        while self.lines.len() < 3 && !self.eof {
+            buf.clear();
+            match self.reader.read_until(b'\n', &mut buf) {
+                Ok(0) => {
+                    self.eof = true;
+                    break;
+                }
+                Ok(_) => {}
+                Err(error) => {
+                    // The line that could not be read comes after the lookahead.
+                    self.state.diagnostics.push(Diagnostic {
+                        line: self.state.line_number + self.lines.len() + 1,
+                        severity: Severity::Error,
+                        kind: DiagnosticKind::ReadError,
+                        message: format!("Failed to read the input: {}.", error),
+                    });
+                    self.error = Some(error);
+                    self.eof = true;
+                    break;
+                }
+            }
+
+            let mut chunk = &buf[..];
+            if chunk.ends_with(b"\n") {
+                chunk = &chunk[..chunk.len() - 1];
+            }
+            if chunk.ends_with(b"\r") {
+                chunk = &chunk[..chunk.len() - 1];
+            }
+
+            // A lone carriage return also ends a line.
+            let chunk = String::from_utf8_lossy(chunk);
+            for line in chunk.split('\r') {
+                self.lines.push_back(line.to_owned());
+            }
+        }
+    }
+}
+
+impl<R: BufRead> Iterator for DiffReader<R> {
+    type Item = File;
+
+    fn next(&mut self) -> Option<File> {
+        loop {
+            if let Some(file) = self.state.files.pop_front() {
+                return Some(file);
+            }
+
+            if self.finished {
+                return None;
+            }
+
+            self.fill();
+
+            match self.lines.pop_front() {
+                Some(line) => {
+                    self.state.line_number += 1;
+                    self.state.parse_line(
+                        &line,
+                        self.prev_line.as_deref(),
+                        self.lines.front().map(|v| v.as_str()),
+                        self.lines.get(1).map(|v| v.as_str()),
+                    );
+                    self.prev_line = Some(line);
+                }
+                None => {
+                    self.state.finish();
+                    self.finished = true;
+                }
+            }
+        }
+    }
+}
+
+struct ParseState {
+    files: VecDeque<File>,
+    current_file: Option<File>,
+    current_block: Option<Block>,
+    // TODO: Why is this not used.
+    //current_line: Option<Line>,
+    old_line: Option<usize>,
+    // Next line number in each parent of a combined diff.
+    parent_lines: Vec<Option<usize>>,
+    new_line: Option<usize>,
+    possible_old_name: Option<String>,
+    possible_new_name: Option<String>,
+    line_number: usize,
+    block_line_number: usize,
+    // Index in the current block of the first word of the line being read
+    // from a `--word-diff=porcelain` diff.
+    word_line_start: usize,
+    // Hunks of the `GIT binary patch` being read, and the one still collecting data.
+    binary_hunks: Option<Vec<BinaryHunk>>,
+    binary_hunk: Option<(BinaryPatchKind, Option<usize>, Vec<u8>)>,
+    // Hunk of a context diff, converted into a block once complete.
+    context_hunk: Option<ContextHunk>,
+    // Whether the current file is a normal diff (`diff` without -u or -c).
+    is_normal_diff: bool,
+    // Whether the current file was started by a header line like `diff` or
+    // `Index:`, so its `---`/`+++` lines do not start another file.
+    file_has_header: bool,
+    // Inside the `Property changes on:` section of a Subversion diff.
+    in_properties: bool,
+    // Inside the `#` lines that start a `hg export` patch.
+    in_hg_header: bool,
+    // Revisions from the `# HG changeset patch` preamble.
+    hg_node: Option<String>,
+    hg_parent: Option<String>,
+    // Prefixes of the file names, from the configuration or detected on the
+    // `diff --git` line of the current file.
+    src_prefix: Option<String>,
+    dst_prefix: Option<String>,
+    no_prefix: bool,
+    file_prefixes: Option<(String, String)>,
+    // Paths of the `diff --git` line the detected prefixes come from.
+    git_diff_paths: Option<(String, String)>,
+    languages: LanguageRegistry,
+    // Deleted file waiting for a new file of another type with its name.
+    deleted_file: Option<File>,
+    diagnostics: Vec<Diagnostic>,
+}
+
+impl ParseState {
+    fn new() -> ParseState {
+        ParseState {
+            files: VecDeque::new(),
+            current_file: None,
+            current_block: None,
+            old_line: None,
+            parent_lines: Vec::new(),
+            new_line: None,
+            possible_old_name: None,
+            possible_new_name: None,
+            line_number: 0,
+            block_line_number: 0,
+            word_line_start: 0,
+            binary_hunks: None,
+            binary_hunk: None,
+            context_hunk: None,
+            is_normal_diff: false,
+            file_has_header: false,
+            in_properties: false,
+            in_hg_header: false,
+            hg_node: None,
+            hg_parent: None,
+            src_prefix: None,
+            dst_prefix: None,
+            no_prefix: false,
+            file_prefixes: None,
+            git_diff_paths: None,
+            deleted_file: None,
+            languages: LanguageRegistry::default(),
+            diagnostics: Vec::new(),
+        }
+    }
+
+    fn diagnose(&mut self, severity: Severity, kind: DiagnosticKind, message: &str) {
+        self.diagnostics.push(Diagnostic {
+            line: self.line_number,
+            severity,
+            kind,
+            message: message.to_owned(),
+        });
+    }
+
+    // Hunk ranges omit the line count when it is 1.
+    fn parse_count(&mut self, value: Option<&str>) -> Option<usize> {
+        match value {
+            Some(value) => self.parse_number(value),
+            _ => Some(1),
+        }
+    }
+
+    fn parse_number(&mut self, value: &str) -> Option<usize> {
+        match value.parse() {
+            Ok(number) => Some(number),
+            Err(_) => {
+                self.diagnose(
+                    Severity::Error,
+                    DiagnosticKind::InvalidNumber,
+                    &format!("Invalid number \"{}\".", value),
+                );
+                None
+            }
+        }
+    }
+
+    fn parse_line(
+        &mut self,
+        line: &str,
+        prev_line: Option<&str>,
+        next_line: Option<&str>,
+        after_next_line: Option<&str>,
+    ) {
+        lazy_static! {
+            // Diff
+            static ref OLD_MODE: Regex = Regex::new(r#"old mode (\d{6})"#).unwrap();
+            static ref NEW_MODE: Regex = Regex::new(r#"new mode (\d{6})"#).unwrap();
+            static ref DELETED_FILE_MODE: Regex = Regex::new(r#"deleted file mode (\d{6})"#).unwrap();
+            static ref NEW_FILE_MODE: Regex = Regex::new(r#"new file mode (\d{6})"#).unwrap();
+
+            static ref COPY_FROM: Regex = Regex::new(r#"^copy from (.+)$"#).unwrap();
+            static ref COPY_TO: Regex = Regex::new(r#"^copy to (.+)$"#).unwrap();
+
+            static ref RENAME_FROM: Regex = Regex::new(r#"^rename from (.+)$"#).unwrap();
+            static ref RENAME_TO: Regex = Regex::new(r#"^rename to (.+)$"#).unwrap();
+
+            static ref SIMILARITY_INDEX: Regex = Regex::new(r#"similarity index (\d+)%"#).unwrap();
+            static ref DISSIMILARITY_INDEX: Regex = Regex::new(r#"dissimilarity index (\d+)%"#).unwrap();
+            static ref INDEX: Regex = Regex::new(r#"index ([0-9a-z]+)\.\.([0-9a-z]+)\s*(\d{6})?"#).unwrap();
+
+            static ref BINARY_FILES: Regex = Regex::new(r#"^Binary files (.*) differ$"#).unwrap();
+            static ref BINARY_DIFF: Regex = Regex::new(r#"GIT binary patch"#).unwrap();
+
+            // Combined Diff
+            static ref COMBINED_INDEX: Regex = Regex::new(r#"index ([0-9a-z]+(?:,[0-9a-z]+)+)\.\.([0-9a-z]+)"#).unwrap();
+            static ref COMBINED_MODE: Regex = Regex::new(r#"mode (\d{6}(?:,\d{6})+)\.\.(\d{6})"#).unwrap();
+            static ref COMBINED_NEW_FILE: Regex = Regex::new(r#"new file mode (\d{6})"#).unwrap();
+            static ref COMBINED_DELETED_FILE: Regex = Regex::new(r#"deleted file mode (\d{6}),(\d{6})"#).unwrap();
+
+            static ref GIT_DIFF_START: Regex = Regex::new(r#"^diff --git (.+)$"#).unwrap();
+            static ref GENERIC_DIFF_START: Regex = Regex::new(r#"^diff(?:\s+-\S+)*\s+(\S+)\s+(\S+)$"#).unwrap();
+        }
+
+        if self.binary_hunks.is_some() && self.parse_binary_line(line) {
+            return;
+        }
+
+        if self.parse_context_line(line, next_line, after_next_line) {
+            return;
+        }
+
+        if self.parse_normal_line(line, next_line) {
+            return;
+        }
+
+        if self.parse_vcs_line(line, next_line) {
+            return;
+        }
+
+        if line.is_empty() || line.starts_with("*") {
+            return;
+        }
+
+        if line.starts_with("diff") {
+            //println!("got new file");
+            self.start_file();
+
+            self.file_has_header = true;
+            self.current_file.as_mut().unwrap().is_git_diff = GIT_DIFF_START.is_match(line)
+                || line.starts_with("diff --cc ")
+                || line.starts_with("diff --combined ");
+
+            if let Some(captures) = GIT_DIFF_START.captures(line) {
+                if let Some((old_name, new_name)) = quote::split_paths(&captures[1], " ") {
+                    if self.src_prefix.is_none() || self.dst_prefix.is_none() {
+                        self.file_prefixes = Some(detect_prefixes(&old_name, &new_name));
+                        self.git_diff_paths = Some((old_name.to_owned(), new_name.to_owned()));
+                    }
+                    self.possible_old_name = Some(self.strip_prefix(old_name, true));
+                    self.possible_new_name = Some(self.strip_prefix(new_name, false));
+                }
+            } else if let Some(captures) = GENERIC_DIFF_START.captures(line) {
+                self.possible_old_name =
+                    get_filename(None, &captures[1]).map(|name| self.strip_prefix(name, true));
+                self.possible_new_name =
+                    get_filename(None, &captures[2]).map(|name| self.strip_prefix(name, false));
+            }
+            return;
+        }
+
+        if
+        // If we do not have a file yet, create one.
+        self.current_file.is_none()
+            || (
+                // We already have some file in progress that has no header of its own.
+                !self.file_has_header
+                    && (
+                        // If we get to an old file path header line
+                        line.starts_with(OLD_FILE_NAME_HEADER)
+                        // And it's followed by the new file path header...
+                        && next_line.is_some_and(|v| v.starts_with(NEW_FILE_NAME_HEADER))
+                        // ...and the hunk header line.
+                        && after_next_line.is_some_and(|v| v.starts_with(HUNK_HEADER_PREFIX))
+                    )
+            )
+        {
+            self.start_file();
+        }
+
+        if line.starts_with(OLD_FILE_NAME_HEADER)
+            && next_line.is_some_and(|v| v.starts_with(NEW_FILE_NAME_HEADER))
+            && after_next_line.is_none()
+            && !self.block_is_open()
+        {
+            self.diagnose(
+                Severity::Error,
+                DiagnosticKind::TruncatedInput,
+                "File header is not followed by any hunk.",
+            );
+        }
+
+        if (next_line.is_some()
+            && line.starts_with(OLD_FILE_NAME_HEADER)
+            && next_line.unwrap().starts_with(NEW_FILE_NAME_HEADER))
+            || (prev_line.is_some()
+                && line.starts_with(NEW_FILE_NAME_HEADER)
+                && prev_line.unwrap().starts_with(OLD_FILE_NAME_HEADER))
+        {
+            if self.current_file.is_some()
+                && self.current_file.as_ref().unwrap().old_name.is_none()
+                && line.starts_with("--- ")
+            {
+                let (line, revision) = split_revision(line);
+                let old_name = get_src_filename(line);
+                if let Some(name) = &old_name {
+                    self.confirm_prefixes(name, true, true);
+                }
+                let old_name = old_name.map(|name| self.strip_prefix(name, true));
+                if let Some(file) = self.current_file.as_mut() {
+                    file.old_name = old_name;
+                    if revision.is_some() {
+                        file.revision_before = revision;
+                    }
+                }
+                return;
+            }
+
+            if self.current_file.is_some()
+                && self.current_file.as_ref().unwrap().new_name.is_none()
+                && line.starts_with("+++ ")
+            {
+                let (line, revision) = split_revision(line);
+                let new_name = get_dst_filename(line);
+                if let Some(name) = &new_name {
+                    self.confirm_prefixes(name, false, true);
+                }
+                let new_name = new_name.map(|name| self.strip_prefix(name, false));
+                if let Some(file) = self.current_file.as_mut() {
+                    file.new_name = new_name;
+                    if revision.is_some() {
+                        file.revision_after = revision;
+                    }
+                }
+                return;
+            }
+        }
+
+        if (self.current_file.is_some() && line.starts_with(HUNK_HEADER_PREFIX))
+            || (self
+                .current_file
+                .as_ref()
+                .map(|file| file.is_git_diff && file.old_name.is_some() && file.new_name.is_some())
+                == Some(true)
+                && self.current_block.is_none())
+        {
+            self.start_block(line);
+            return;
+        }
+
+        if line.starts_with(NO_NEWLINE_PREFIX) {
+            self.mark_no_newline();
+            return;
+        }
+
+        if self.current_block.is_some() && line == WORD_DIFF_LINE_END {
+            self.end_word_line();
+            return;
+        }
+
+        if self.current_block.is_some()
+            && (line.starts_with("+") || line.starts_with("-") || line.starts_with(" "))
+        {
+            self.create_line(line);
+            return;
+        }
+
+        /*
+         * Git diffs provide more information regarding files modes, renames, copies,
+         * commits between changes and similarity indexes
+         */
+        if let Some(captures) = OLD_MODE.captures(line) {
+            if let Some(file) = self.current_file.as_mut() {
+                file.old_mode = Some(vec![captures.get(1).unwrap().as_str().to_owned()]);
+            }
+        } else if let Some(captures) = NEW_MODE.captures(line) {
+            if let Some(file) = self.current_file.as_mut() {
+                file.new_mode = captures.get(1).map(|v| v.as_str().to_owned());
+            }
+        } else if let Some(captures) = DELETED_FILE_MODE.captures(line) {
+            if let Some(file) = self.current_file.as_mut() {
+                file.deleted_file_mode = captures.get(1).map(|v| v.as_str().to_owned());
+                file.is_deleted = true;
+            }
+        } else if let Some(captures) = NEW_FILE_MODE.captures(line) {
+            if let Some(file) = self.current_file.as_mut() {
+                file.new_file_mode = captures.get(1).map(|v| v.as_str().to_owned());
+                file.is_new = true;
+            }
+        } else if let Some(captures) = COPY_FROM.captures(line) {
+            self.confirm_prefixes(&quote::unquote_path(&captures[1]), true, false);
+            self.possible_old_name = Some(quote::unquote_path(&captures[1]));
+            if let Some(file) = self.current_file.as_mut() {
+                file.is_copy = true;
+            }
+        } else if let Some(captures) = COPY_TO.captures(line) {
+            self.confirm_prefixes(&quote::unquote_path(&captures[1]), false, false);
+            self.possible_new_name = Some(quote::unquote_path(&captures[1]));
+            if let Some(file) = self.current_file.as_mut() {
+                file.is_copy = true;
+            }
+        } else if let Some(captures) = RENAME_FROM.captures(line) {
+            self.confirm_prefixes(&quote::unquote_path(&captures[1]), true, false);
+            self.possible_old_name = Some(quote::unquote_path(&captures[1]));
+            if let Some(file) = self.current_file.as_mut() {
+                file.is_rename = true;
+            }
+        } else if let Some(captures) = RENAME_TO.captures(line) {
+            self.confirm_prefixes(&quote::unquote_path(&captures[1]), false, false);
+            self.possible_new_name = Some(quote::unquote_path(&captures[1]));
+            if let Some(file) = self.current_file.as_mut() {
+                file.is_rename = true;
+            }
+        } else if let Some(captures) = BINARY_FILES.captures(line) {
+            let names = quote::split_paths(&captures[1], " and ").map(|(old_name, new_name)| {
+                (
+                    self.strip_prefix(old_name, true),
+                    self.strip_prefix(new_name, false),
+                )
+            });
+            if let Some(file) = self.current_file.as_mut() {
+                file.is_binary = true;
+                if let Some((old_name, new_name)) = names {
+                    file.old_name = Some(old_name);
+                    file.new_name = Some(new_name);
+                }
+            }
+            self.start_block("Binary file");
+        } else if let Some(_captures) = BINARY_DIFF.captures(line) {
+            if let Some(file) = self.current_file.as_mut() {
+                file.is_binary = true;
+            }
+            self.start_block(line);
+            self.binary_hunks = Some(Vec::new());
+        } else if let Some(captures) = SIMILARITY_INDEX.captures(line) {
+            let percentage = self.parse_number(&captures[1]);
+            if let Some(file) = self.current_file.as_mut() {
+                file.unchanged_percentage = percentage;
+            }
+        } else if let Some(captures) = DISSIMILARITY_INDEX.captures(line) {
+            let percentage = self.parse_number(&captures[1]);
+            if let Some(file) = self.current_file.as_mut() {
+                file.changed_percentage = percentage;
+            }
+        } else if let Some(captures) = INDEX.captures(line) {
+            if let Some(file) = self.current_file.as_mut() {
+                file.checksum_before = Some(vec![captures.get(1).unwrap().as_str().to_owned()]);
+                file.checksum_after = captures.get(2).map(|v| v.as_str().to_owned());
+                if let Some(mode) = captures.get(3) {
+                    file.mode = Some(mode.as_str().to_owned());
+                }
+            }
+        } else if let Some(captures) = COMBINED_INDEX.captures(line) {
+            if let Some(file) = self.current_file.as_mut() {
+                file.checksum_before = Some(captures[1].split(',').map(|v| v.to_owned()).collect());
+                file.checksum_after = captures.get(2).map(|v| v.as_str().to_owned());
+            }
+        } else if let Some(captures) = COMBINED_MODE.captures(line) {
+            if let Some(file) = self.current_file.as_mut() {
+                file.old_mode = Some(captures[1].split(',').map(|v| v.to_owned()).collect());
+                file.new_mode = captures.get(2).map(|v| v.as_str().to_owned());
+            }
+        } else if let Some(captures) = COMBINED_NEW_FILE.captures(line) {
+            if let Some(file) = self.current_file.as_mut() {
+                file.new_file_mode = captures.get(1).map(|v| v.as_str().to_owned());
+                file.is_new = true;
+            }
+        } else if let Some(captures) = COMBINED_DELETED_FILE.captures(line) {
+            if let Some(file) = self.current_file.as_mut() {
+                file.deleted_file_mode = captures.get(1).map(|v| v.as_str().to_owned());
+                file.is_deleted = true;
+            }
+        }
+    }
+
+    fn start_file(&mut self) {
+        self.save_block();
+        self.save_file();
+
+        let file = File::new();
+        self.current_file = Some(file);
+        self.is_normal_diff = false;
+        self.file_has_header = false;
+        self.in_properties = false;
+        self.file_prefixes = None;
+        self.git_diff_paths = None;
+    }
+
+    // Drops the prefixes detected on the `diff --git` line when a later header
+    // names the file differently. The `---`/`+++` names are prefixed like the
+    // `diff --git` paths, the `rename`/`copy` ones are not. A cross-directory
+    // rename made with `--no-prefix` looks prefixed until its `rename from`.
+    fn confirm_prefixes(&mut self, name: &str, is_src: bool, is_prefixed: bool) {
+        let (path, prefix) = match (&self.git_diff_paths, &self.file_prefixes) {
+            (Some((old_path, new_path)), Some((src, dst))) => {
+                if is_src {
+                    (old_path, src)
+                } else {
+                    (new_path, dst)
+                }
+            }
+            _ => return,
+        };
+        let expected = if is_prefixed {
+            Some(path.as_str())
+        } else {
+            path.strip_prefix(prefix.as_str())
+        };
+        if name == "/dev/null" || expected == Some(name) {
+            return;
+        }
+
+        if let Some((old_path, new_path)) = self.git_diff_paths.take() {
+            self.possible_old_name = Some(old_path);
+            self.possible_new_name = Some(new_path);
+        }
+        self.file_prefixes = Some((String::new(), String::new()));
+    }
+
+    // Removes the source or destination prefix from a file name. Without a
+    // configured or detected prefix, the usual git prefixes are removed.
+    fn strip_prefix(&self, filename: String, is_src: bool) -> String {
+        lazy_static! {
+            static ref PREFIXES: Vec<&'static str> = vec!["a/", "b/", "i/", "w/", "c/", "o/"];
+        }
+
+        if self.no_prefix {
+            return filename;
+        }
+
+        let configured = if is_src {
+            &self.src_prefix
+        } else {
+            &self.dst_prefix
+        };
+        let detected = self
+            .file_prefixes
+            .as_ref()
+            .map(|(src, dst)| if is_src { src } else { dst });
+
+        let prefix = match configured.as_ref().or(detected) {
+            Some(prefix) => Some(prefix.as_str()),
+            _ => PREFIXES
+                .iter()
+                .find(|prefix| filename.starts_with(*prefix))
+                .cloned(),
+        };
+
+        match prefix {
+            Some(prefix) if filename.starts_with(prefix) => filename[prefix.len()..].to_owned(),
+            _ => filename,
+        }
+    }
+
+    fn start_file_with_header(&mut self, old_name: Option<String>, new_name: Option<String>) {
+        self.start_file();
+        self.file_has_header = true;
+        self.possible_old_name = old_name;
+        self.possible_new_name = new_name;
+    }
+
+    // Handles the headers of Subversion, Mercurial and Perforce diffs. They
+    // only start a file where the next line confirms them, so the same text
+    // in a preamble or a commit message is left alone.
+    fn parse_vcs_line(&mut self, line: &str, next_line: Option<&str>) -> bool {
+        lazy_static! {
+            static ref SVN_INDEX: Regex = Regex::new(r#"^Index: (.+)$"#).unwrap();
+            static ref SVN_SEPARATOR: Regex = Regex::new(r#"^={10,}$"#).unwrap();
+            static ref SVN_PROPERTIES: Regex =
+                Regex::new(r#"^Property changes on: (.+)$"#).unwrap();
+            static ref SVN_PROPERTIES_SEPARATOR: Regex = Regex::new(r#"^_{10,}$"#).unwrap();
+            static ref SVN_PROPERTY: Regex =
+                Regex::new(r#"^(Added|Modified|Deleted|Name): (.+)$"#).unwrap();
+            static ref SVN_PROPERTY_VALUE: Regex = Regex::new(r#"^(?:   )?([-+]) ?(.*)$"#).unwrap();
+            static ref HG_DIFF_START: Regex =
+                Regex::new(r#"^diff -r (\w+)(?: -r (\w+))? (.+)$"#).unwrap();
+            static ref HG_NODE: Regex = Regex::new(r#"^# Node ID (\w+)"#).unwrap();
+            static ref HG_PARENT: Regex = Regex::new(r#"^# Parent +(\w+)"#).unwrap();
+            static ref P4_HEADER: Regex = Regex::new(r#"^==== (.+?) ====(?: .*)?$"#).unwrap();
+            static ref P4_PATH: Regex =
+                Regex::new(r#"^(.+?)(?:#(\d+|none|head|have))?(?: \([^)]*\))?$"#).unwrap();
+        }
+
+        let is_svn_index =
+            SVN_INDEX.is_match(line) && next_line.is_some_and(|v| SVN_SEPARATOR.is_match(v));
+        let is_file_header = line.starts_with(OLD_FILE_NAME_HEADER)
+            && next_line.is_some_and(|v| v.starts_with(NEW_FILE_NAME_HEADER));
+
+        if self.in_properties && !is_svn_index && !is_file_header {
+            if let Some(captures) = SVN_PROPERTY.captures(line) {
+                let action = match &captures[1] {
+                    "Added" => PropertyAction::Added,
+                    "Deleted" => PropertyAction::Deleted,
+                    _ => PropertyAction::Modified,
+                };
+                if let Some(file) = self.current_file.as_mut() {
+                    file.properties.push(PropertyChange {
+                        name: captures[2].to_owned(),
+                        action,
+                        old_value: None,
+                        new_value: None,
+                    });
+                }
+                return true;
+            }
+
+            if line.is_empty() || line.starts_with("##") || line.starts_with("___") {
+                return true;
+            }
+
+            if let Some(captures) = SVN_PROPERTY_VALUE.captures(line) {
+                if let Some(property) = self
+                    .current_file
+                    .as_mut()
+                    .and_then(|file| file.properties.last_mut())
+                {
+                    let value = if &captures[1] == "-" {
+                        &mut property.old_value
+                    } else {
+                        &mut property.new_value
+                    };
+                    match value {
+                        Some(value) => {
+                            value.push('\n');
+                            value.push_str(&captures[2]);
+                        }
+                        None => *value = Some(captures[2].to_owned()),
+                    }
+                }
+                return true;
+            }
+        }
+        if self.in_properties && is_file_header {
+            // The properties close their file, the header belongs to the next one.
+            self.file_has_header = false;
+        }
+        self.in_properties = false;
+
+        if let Some(captures) = SVN_PROPERTIES.captures(line) {
+            if next_line.is_some_and(|v| SVN_PROPERTIES_SEPARATOR.is_match(v)) {
+                let path = captures[1].to_owned();
+                let is_current_file = self.current_file.as_ref().is_some_and(|file| {
+                    file.new_name.as_ref().or(self.possible_new_name.as_ref()) == Some(&path)
+                });
+                if !is_current_file {
+                    self.start_file_with_header(Some(path.to_owned()), Some(path));
+                }
+                self.save_block();
+                self.in_properties = true;
+                return true;
+            }
+        }
+
+        if is_svn_index {
+            let path = line["Index: ".len()..].to_owned();
+            self.start_file_with_header(Some(path.to_owned()), Some(path));
+            return true;
+        }
+
+        if line.starts_with("Cannot display: file marked as a binary type.") {
+            if let Some(file) = self.current_file.as_mut() {
+                file.is_binary = true;
+            }
+            return true;
+        }
+
+        if line.starts_with("# HG changeset patch")
+            && next_line.is_some_and(|v| v.starts_with("# "))
+        {
+            self.start_file();
+            self.hg_node = None;
+            self.hg_parent = None;
+            self.in_hg_header = true;
+            return true;
+        }
+
+        if self.in_hg_header && line.starts_with('#') {
+            if let Some(captures) = HG_NODE.captures(line) {
+                self.hg_node = Some(captures[1].to_owned());
+            }
+            // Merges list a second parent, the first one is what the diff is against.
+            if let Some(captures) = HG_PARENT.captures(line) {
+                if self.hg_parent.is_none() {
+                    self.hg_parent = Some(captures[1].to_owned());
+                }
+            }
+            return true;
+        }
+        self.in_hg_header = false;
+
+        if let Some(captures) = HG_DIFF_START.captures(line) {
+            let path = captures[3].to_owned();
+            self.start_file_with_header(Some(path.to_owned()), Some(path));
+            if let Some(file) = self.current_file.as_mut() {
+                file.revision_before = Some(captures[1].to_owned());
+                file.revision_after = captures.get(2).map(|v| v.as_str().to_owned());
+            }
+            return true;
+        }
+
+        if let Some(captures) = P4_HEADER.captures(line) {
+            let sides = captures[1]
+                .splitn(2, " - ")
+                .map(|side| {
+                    let captures = P4_PATH.captures(side)?;
+                    let path = Some(captures[1].to_owned()).filter(|path| path != "<none>");
+                    let revision = captures.get(2).map(|v| v.as_str().to_owned());
+                    Some((path, revision))
+                })
+                .collect::<Option<Vec<(Option<String>, Option<String>)>>>();
+            let sides = match sides {
+                Some(sides) => sides,
+                None => {
+                    self.diagnose(
+                        Severity::Error,
+                        DiagnosticKind::InvalidFileHeader,
+                        "Perforce file header has an empty path.",
+                    );
+                    return true;
+                }
+            };
+
+            match (sides.first().cloned(), sides.get(1).cloned()) {
+                // `p4 diff2` compares two depot revisions.
+                (Some((old_path, old_revision)), Some((new_path, Some(new_revision)))) => {
+                    self.start_file_with_header(old_path.to_owned(), new_path.to_owned());
+                    if let Some(file) = self.current_file.as_mut() {
+                        file.is_new = old_path.is_none();
+                        file.is_deleted = new_path.is_none();
+                        file.revision_before = old_revision;
+                        file.revision_after = Some(new_revision);
+                    }
+                }
+                // `p4 diff` compares a depot revision with the workspace file.
+                (Some((path, revision)), Some(_)) => {
+                    self.start_file_with_header(path.to_owned(), path);
+                    if let Some(file) = self.current_file.as_mut() {
+                        file.revision_before = revision;
+                    }
+                }
+                // `p4 describe` shows the revision a change submitted.
+                (Some((path, revision)), None) => {
+                    self.start_file_with_header(path.to_owned(), path);
+                    if let Some(file) = self.current_file.as_mut() {
+                        file.revision_after = revision;
+                    }
+                }
+                _ => return false,
+            }
+            return true;
+        }
+
+        false
+    }
+
+    // "\ No newline at end of file" refers to the line before it.
+    fn mark_no_newline(&mut self) {
+        if let Some(last) = self
+            .current_block
+            .as_mut()
+            .and_then(|block| block.lines.last_mut())
+        {
+            last.no_newline_at_eof = true;
+        }
+    }
+
+    // Handles the file headers and hunks of context diffs (`diff -c`).
+    fn parse_context_line(
+        &mut self,
+        line: &str,
+        next_line: Option<&str>,
+        after_next_line: Option<&str>,
+    ) -> bool {
+        lazy_static! {
+            static ref OLD_RANGE: Regex =
+                Regex::new(r#"^\*\*\* (\d+)(?:,(\d+))? \*\*\*\*$"#).unwrap();
+            static ref NEW_RANGE: Regex = Regex::new(r#"^--- (\d+)(?:,(\d+))? ----$"#).unwrap();
+        }
+
+        if line.starts_with(CONTEXT_OLD_FILE_NAME_HEADER)
+            && next_line.is_some_and(|v| v.starts_with(OLD_FILE_NAME_HEADER))
+            && after_next_line == Some(CONTEXT_HUNK_SEPARATOR)
+        {
+            self.save_context_hunk();
+            if !self.file_has_header
+                || self
+                    .current_file
+                    .as_ref()
+                    .is_none_or(|file| file.old_name.is_some() || !file.blocks.is_empty())
+            {
+                self.start_file();
+            }
+            let old_name = get_context_filename(line).map(|name| self.strip_prefix(name, true));
+            if let Some(file) = self.current_file.as_mut() {
+                file.old_name = old_name;
+            }
+            return true;
+        }
+
+        if self.context_hunk.is_none() && self.current_file.is_some() {
+            let is_new_name_header = self.current_file.as_ref().is_some_and(|file| {
+                file.old_name.is_some() && file.new_name.is_none() && file.blocks.is_empty()
+            }) && line.starts_with(OLD_FILE_NAME_HEADER)
+                && next_line == Some(CONTEXT_HUNK_SEPARATOR);
+            if is_new_name_header {
+                let new_name =
+                    get_context_filename(line).map(|name| self.strip_prefix(name, false));
+                if let Some(file) = self.current_file.as_mut() {
+                    file.new_name = new_name;
+                }
+                return true;
+            }
+        }
+
+        if line == CONTEXT_HUNK_SEPARATOR && self.current_file.is_some() {
+            self.save_context_hunk();
+            self.save_block();
+            self.context_hunk = Some(ContextHunk::new(self.line_number + 1));
+            return true;
+        }
+
+        if self.context_hunk.is_none() {
+            return false;
+        }
+
+        if let Some(captures) = OLD_RANGE.captures(line) {
+            let start = self.parse_number(&captures[1]).unwrap_or(0);
+            let end = captures.get(2).and_then(|v| self.parse_number(v.as_str()));
+            self.context_hunk.as_mut().unwrap().old_range = Some((start, end));
+            return true;
+        }
+
+        if let Some(captures) = NEW_RANGE.captures(line) {
+            let start = self.parse_number(&captures[1]).unwrap_or(0);
+            let end = captures.get(2).and_then(|v| self.parse_number(v.as_str()));
+            self.context_hunk.as_mut().unwrap().new_range = Some((start, end));
+            return true;
+        }
+
+        if line.starts_with("! ")
+            || line.starts_with("+ ")
+            || line.starts_with("- ")
+            || line.starts_with("  ")
+            || line.starts_with(NO_NEWLINE_PREFIX)
+        {
+            self.context_hunk.as_mut().unwrap().push_line(line);
+            return true;
+        }
+
+        // Blank lines are skipped everywhere else too.
+        if line.is_empty() {
+            return true;
+        }
+
+        self.save_context_hunk();
+        false
+    }
+
+    // Handles the change commands (`3c3`, `5,7d4`, `10a11,12`) and `<`/`>`
+    // lines of normal diffs, turning every command into a block.
+    fn parse_normal_line(&mut self, line: &str, next_line: Option<&str>) -> bool {
+        lazy_static! {
+            static ref NORMAL_COMMAND: Regex =
+                Regex::new(r#"^(\d+)(?:,(\d+))?([acd])(\d+)(?:,(\d+))?$"#).unwrap();
+        }
+
+        if let Some(captures) = NORMAL_COMMAND.captures(line) {
+            // Every command is followed by the lines it removes or adds, and
+            // a normal diff only starts where no git or unified file header
+            // expects hunks.
+            let has_lines = next_line.is_some_and(|v| v.starts_with('<') || v.starts_with('>'));
+            let can_start = self.is_normal_diff
+                || (self.current_block.is_none()
+                    && self.current_file.as_ref().is_none_or(|file| {
+                        file.blocks.is_empty() && !file.is_git_diff && file.old_name.is_none()
+                    }));
+            if has_lines && can_start {
+                let command = &captures[3];
+                let (old_start, old_count) =
+                    self.parse_normal_range(&captures[1], captures.get(2), command == "a");
+                let (new_start, new_count) =
+                    self.parse_normal_range(&captures[4], captures.get(5), command == "d");
+
+                if self.current_file.is_none() {
+                    self.start_file();
+                }
+                self.start_block(&format!(
+                    "@@ -{},{} +{},{} @@",
+                    old_start, old_count, new_start, new_count
+                ));
+                self.is_normal_diff = true;
+                return true;
+            }
+        }
+
+        if !self.is_normal_diff || self.current_block.is_none() {
+            return false;
+        }
+
+        if line.starts_with("<") {
+            self.create_line(&format!("-{}", line.get(2..).unwrap_or("")));
+        } else if line.starts_with(">") {
+            self.create_line(&format!("+{}", line.get(2..).unwrap_or("")));
+        } else if line != "---" {
+            return false;
+        }
+        true
+    }
+
+    // Empty ranges give the line they come after, like unified hunk headers.
+    fn parse_normal_range(
+        &mut self,
+        start: &str,
+        end: Option<regex::Match>,
+        is_empty: bool,
+    ) -> (usize, usize) {
+        let start = self.parse_number(start).unwrap_or(0);
+        if is_empty {
+            return (start, 0);
+        }
+        let end = end
+            .and_then(|end| self.parse_number(end.as_str()))
+            .unwrap_or(start);
+        (start, (end + 1).saturating_sub(start))
+    }
+
+    fn save_context_hunk(&mut self) {
+        if let Some(hunk) = self.context_hunk.take() {
+            if hunk.old_range.is_none() && hunk.new_range.is_none() {
+                return;
+            }
+
+            if !hunk.sections_match() {
+                let line_number = self.line_number;
+                self.line_number = hunk.line_number;
+                self.diagnose(
+                    Severity::Error,
+                    DiagnosticKind::HunkLengthMismatch,
+                    "Context hunk sections do not have the same unchanged lines.",
+                );
+                self.line_number = line_number;
+            }
+
+            let (header, lines) = hunk.to_unified();
+            self.start_block(&header);
+            self.block_line_number = hunk.line_number;
+            for line in lines {
+                if line.starts_with(NO_NEWLINE_PREFIX) {
+                    self.mark_no_newline();
+                } else {
+                    self.create_line(&line);
+                }
+            }
+            self.save_block();
+        }
+    }
+
+    // Returns false once the line is no longer part of the binary patch.
+    fn parse_binary_line(&mut self, line: &str) -> bool {
+        lazy_static! {
+            static ref BINARY_HUNK: Regex = Regex::new(r#"^(literal|delta) (\d+)$"#).unwrap();
+        }
+
+        if self.binary_hunk.is_some() {
+            if line.is_empty() {
+                self.save_binary_hunk();
+                return true;
+            }
+
+            if let Some(bytes) = binary::decode_base85_line(line) {
+                self.binary_hunk.as_mut().unwrap().2.extend(bytes);
+                return true;
+            }
+
+            self.save_binary_patch();
+            return false;
+        }
+
+        if let Some(captures) = BINARY_HUNK.captures(line) {
+            let kind = if &captures[1] == "literal" {
+                BinaryPatchKind::Literal
+            } else {
+                BinaryPatchKind::Delta
+            };
+            let size = self.parse_number(&captures[2]);
+            self.binary_hunk = Some((kind, size, Vec::new()));
+            return true;
+        }
+
+        self.save_binary_patch();
+        false
+    }
+
+    fn save_binary_hunk(&mut self) {
+        if let Some((kind, size, compressed)) = self.binary_hunk.take() {
+            // A size that failed to parse was reported already.
+            let size = match size {
+                Some(size) => size,
+                None => return,
+            };
+            match binary::inflate(&compressed, size) {
+                Some(ref data) if data.len() != size => self.diagnose(
+                    Severity::Error,
+                    DiagnosticKind::InvalidBinaryPatch,
+                    &format!(
+                        "Binary hunk inflates to {} bytes but its header declares {}.",
+                        data.len(),
+                        size,
+                    ),
+                ),
+                Some(data) => {
+                    if let Some(hunks) = self.binary_hunks.as_mut() {
+                        hunks.push(BinaryHunk {
+                            kind,
+                            size: data.len(),
+                            data,
+                        })
+                    }
+                }
+                None => self.diagnose(
+                    Severity::Error,
+                    DiagnosticKind::InvalidBinaryPatch,
+                    &format!(
+                        "Binary hunk is not valid zlib data of at most the {} bytes its header declares.",
+                        size
+                    ),
+                ),
+            }
+        }
+    }
+
+    fn save_binary_patch(&mut self) {
+        self.save_binary_hunk();
+
+        let mut hunks = match self.binary_hunks.take() {
+            Some(hunks) => hunks.into_iter(),
+            _ => return,
+        };
+        let forward = match hunks.next() {
+            Some(forward) => forward,
+            _ => return,
+        };
+        let reverse = hunks.next();
+
+        if let Some(ref mut file) = self.current_file {
+            let (old_size, new_size) = match forward.kind {
+                BinaryPatchKind::Delta => match binary::delta_sizes(&forward.data) {
+                    Some((old_size, new_size)) => (Some(old_size), Some(new_size)),
+                    _ => (None, None),
+                },
+                BinaryPatchKind::Literal => {
+                    let old_size = match reverse {
+                        Some(ref hunk) if hunk.kind == BinaryPatchKind::Delta => {
+                            binary::delta_sizes(&hunk.data).map(|sizes| sizes.1)
+                        }
+                        Some(ref hunk) => Some(hunk.size),
+                        _ if file.is_new => Some(0),
+                        _ => None,
+                    };
+                    (old_size, Some(forward.size))
+                }
+            };
+
+            file.old_size = old_size;
+            file.new_size = new_size;
+            file.binary_patch_kind = Some(forward.kind.to_owned());
+            file.binary_patch = Some(BinaryPatch { forward, reverse });
+        }
+    }
+
+    fn save_file(&mut self) {
+        self.save_binary_patch();
+        self.save_context_hunk();
+
+        if let Some(ref mut file) = self.current_file {
+            if file.revision_before.is_none() && file.revision_after.is_none() {
+                file.revision_before = self.hg_parent.to_owned();
+                file.revision_after = self.hg_node.to_owned();
+            }
+        }
+
+        if self.current_file.is_some() && {
+            let file = self.current_file.as_mut().unwrap();
+            if file.old_name.is_none() {
+                file.old_name = self.possible_old_name.take();
+            }
+            if file.new_name.is_none() {
+                file.new_name = self.possible_new_name.take();
+            }
+            file.new_name.is_some() || !file.blocks.is_empty()
+        } {
+            let mut file = self.current_file.take().unwrap();
+            set_file_kinds(&mut file);
+            file.language = self.detect_language(&file);
+            file.submodule = get_submodule_change(&file);
+            self.push_file(file);
+        }
+
+        self.possible_old_name = None;
+        self.possible_new_name = None;
+    }
+
+    /*
+     * Git shows a file whose type changed, like a file replaced by a symlink,
+     * as a deleted file followed by a new one with the same name. Deleted
+     * files are held back until the next file is known so both can be marked.
+     */
+    fn push_file(&mut self, mut file: File) {
+        if let Some(mut deleted_file) = self.deleted_file.take() {
+            if file.is_new
+                && file.new_name == deleted_file.old_name
+                && is_type_change(deleted_file.old_kind, file.new_kind)
+            {
+                deleted_file.is_type_change = true;
+                deleted_file.new_kind = file.new_kind;
+                file.is_type_change = true;
+                file.old_kind = deleted_file.old_kind;
+            }
+            self.files.push_back(deleted_file);
+        }
+
+        if file.is_deleted {
+            self.deleted_file = Some(file);
+        } else {
+            self.files.push_back(file);
+        }
+    }
+
+    // The new name tells the language best, a renamed file may have lost its
+    // extension though. Scripts are recognized by their first line.
+    fn detect_language(&self, file: &File) -> Option<String> {
+        let name_language = [&file.new_name, &file.old_name]
+            .iter()
+            .filter_map(|name| name.as_ref())
+            .filter(|name| name.as_str() != "/dev/null")
+            .find_map(|name| self.languages.detect(name));
+
+        // Only names without an extension leave room for a script, a file
+        // named after a language keeps it whatever its first line is.
+        let has_extension = [&file.new_name, &file.old_name]
+            .iter()
+            .filter_map(|name| name.as_ref())
+            .filter(|name| name.as_str() != "/dev/null")
+            .any(|name| {
+                let filename = name.rsplit('/').next().unwrap_or(name);
+                filename.char_indices().any(|(i, c)| c == '.' && i > 0)
+            });
+        if name_language.is_some() || has_extension {
+            return name_language;
+        }
+
+        // The first line of an existing file may not even be in the diff.
+        let is_new = file.is_new || file.old_name.as_deref() == Some("/dev/null");
+        if !is_new {
+            return None;
+        }
+        file.blocks
+            .iter()
+            .flat_map(|block| block.lines.iter())
+            .find(|line| line.new_number == Some(1))
+            .and_then(|line| self.languages.detect_shebang(line.content.get(1..)?))
+    }
+
+    fn finish(&mut self) {
+        self.save_block();
+        self.save_file();
+        if let Some(file) = self.deleted_file.take() {
+            self.files.push_back(file);
+        }
+    }
+
+    fn start_block(&mut self, line: &str) {
+        self.save_block();
+
+        lazy_static! {
+            static ref RANGE1: Regex =
+                Regex::new(r#"^@@ -(\d+)(?:,(\d+))? \+(\d+)(?:,(\d+))? @@ ?(.*)"#).unwrap();
+            static ref RANGE2: Regex =
+                Regex::new(r#"^@@@+ ((?:-\d+(?:,\d+)? )+)\+(\d+)(?:,(\d+))? @@@+ ?(.*)"#).unwrap();
+            static ref PARENT_RANGE: Regex = Regex::new(r#"-(\d+)(?:,(\d+))?"#).unwrap();
+        }
+
+        let mut block = Block::new();
+        block.header = Some(line.to_owned());
+
+        let captures = RANGE1.captures(line);
+        match captures {
+            Some(captures) => {
+                self.current_file
+                    .as_mut()
+                    .map(|file| file.is_combined = false);
+                self.old_line = self.parse_number(&captures[1]);
+                self.parent_lines = Vec::new();
+                self.new_line = self.parse_number(&captures[3]);
+                block.old_start = self.old_line;
+                block.old_count = self.parse_count(captures.get(2).map(|v| v.as_str()));
+                block.new_start = self.new_line;
+                block.new_count = self.parse_count(captures.get(4).map(|v| v.as_str()));
+                block.section_heading = get_section_heading(&captures[5]);
+            }
+            _ => {
+                let captures = RANGE2.captures(line);
+                match captures {
+                    Some(captures) => {
+                        self.current_file
+                            .as_mut()
+                            .map(|file| file.is_combined = true);
+                        for range in PARENT_RANGE.captures_iter(&captures[1]) {
+                            let start = self.parse_number(&range[1]);
+                            let count = self.parse_count(range.get(2).map(|v| v.as_str()));
+                            block.parent_ranges.push(ParentRange {
+                                start: start.unwrap_or(0),
+                                count: count.unwrap_or(0),
+                            });
+                        }
+                        self.parent_lines = block
+                            .parent_ranges
+                            .iter()
+                            .map(|range| Some(range.start))
+                            .collect();
+                        self.old_line = self.parent_lines[0];
+                        self.new_line = self.parse_number(&captures[2]);
+                        block.old_start = self.old_line;
+                        block.old_count = Some(block.parent_ranges[0].count);
+                        block.new_start = self.new_line;
+                        block.new_count = self.parse_count(captures.get(3).map(|v| v.as_str()));
+                        block.section_heading = get_section_heading(&captures[4]);
+                    }
+                    _ => {
+                        if line.starts_with(HUNK_HEADER_PREFIX) {
+                            self.diagnose(
+                                Severity::Error,
+                                DiagnosticKind::InvalidHunkHeader,
+                                "Failed to parse lines, starting in 0.",
+                            );
+                        }
+                        self.old_line = Some(0);
+                        self.parent_lines = Vec::new();
+                        self.new_line = Some(0);
+                        self.current_file
+                            .as_mut()
+                            .map(|file| file.is_combined = false);
+                    }
+                }
+            }
+        }
+
+        self.block_line_number = self.line_number;
+        self.word_line_start = 0;
+        self.current_block = Some(block);
+    }
+
+    fn save_block(&mut self) {
+        if let Some(block) = self.current_block.take() {
+            self.check_block_length(&block);
+
+            if let Some(ref mut file) = self.current_file {
+                file.blocks.push(block);
+            }
+        }
+    }
+
+    fn check_block_length(&mut self, block: &Block) {
+        // Word diffs do not show changes of line breaks, so their lines need
+        // not add up to the header.
+        if self.is_word_diff() {
+            return;
+        }
+
+        let (old_lines, new_lines) = block.line_counts();
+
+        let too_short = block.old_count.is_some_and(|count| old_lines < count)
+            || block.new_count.is_some_and(|count| new_lines < count);
+        let too_long = block.old_count.is_some_and(|count| old_lines > count)
+            || block.new_count.is_some_and(|count| new_lines > count);
+        let parents_mismatch = block
+            .parent_ranges
+            .iter()
+            .enumerate()
+            .any(|(i, range)| block.parent_line_count(i) != range.count);
+
+        if too_short || too_long || parents_mismatch {
+            let line_number = self.line_number;
+            self.line_number = self.block_line_number;
+            self.diagnose(
+                Severity::Error,
+                DiagnosticKind::HunkLengthMismatch,
+                &format!(
+                    "Hunk has {} old and {} new lines but its header declares {} and {}.",
+                    old_lines,
+                    new_lines,
+                    block.old_count.unwrap_or(0),
+                    block.new_count.unwrap_or(0),
+                ),
+            );
+            self.line_number = line_number;
+        }
+    }
+
+    // Whether the current block still expects lines according to its header.
+    fn block_is_open(&self) -> bool {
+        if self.is_word_diff() {
+            return false;
+        }
+        self.current_block.as_ref().is_some_and(|block| {
+            let (old_lines, new_lines) = block.line_counts();
+            block.old_count.is_some_and(|count| old_lines < count)
+                || block.new_count.is_some_and(|count| new_lines < count)
+        })
+    }
+
+    fn is_word_diff(&self) -> bool {
+        self.current_file
+            .as_ref()
+            .is_some_and(|file| file.is_word_diff)
+    }
+
+    /*
+     * `--word-diff=porcelain` puts every word of a line on its own line,
+     * marked like a line of a unified diff, and ends the line with a `~`. The
+     * words read as lines since the previous `~` become a single line.
+     */
+    fn end_word_line(&mut self) {
+        let (block, file) = match (self.current_block.as_mut(), self.current_file.as_mut()) {
+            (Some(block), Some(file)) => (block, file),
+            // A `~` outside of any file ends nothing.
+            _ => return,
+        };
+        let words = block
+            .lines
+            .split_off(self.word_line_start.min(block.lines.len()));
+
+        // Undo the numbering and counting of the words as lines.
+        file.is_word_diff = true;
+        let decrement = |number: &mut Option<usize>| {
+            if let Some(number) = number.as_mut() {
+                *number -= 1;
+            }
+        };
+        for word in words.iter() {
+            match word.line_type {
+                Some(LineType::Inserts) => {
+                    file.added_lines -= 1;
+                    decrement(&mut self.new_line);
+                }
+                Some(LineType::Deletes) => {
+                    file.deleted_lines -= 1;
+                    decrement(&mut self.old_line);
+                }
+                _ => {
+                    decrement(&mut self.old_line);
+                    decrement(&mut self.new_line);
+                }
+            }
+        }
+
+        let has_words = |line_type: LineType| {
+            words
+                .iter()
+                .any(|word| word.line_type.as_ref() == Some(&line_type))
+        };
+        let is_inserted = has_words(LineType::Inserts);
+        let is_deleted = has_words(LineType::Deletes);
+        // A line with both kinds of words, or any unchanged one, is in both
+        // versions of the file.
+        let line_type = if has_words(LineType::Context) || is_inserted == is_deleted {
+            LineType::Context
+        } else if is_inserted {
+            LineType::Inserts
+        } else {
+            LineType::Deletes
+        };
+
+        let mut line = Line::new(String::new());
+        line.segments = words
+            .iter()
+            .map(|word| Segment {
+                line_type: word.line_type.to_owned().unwrap_or(LineType::Context),
+                content: word.content.get(1..).unwrap_or("").to_owned(),
+            })
+            .collect();
+        line.no_newline_at_eof = words.iter().any(|word| word.no_newline_at_eof);
+
+        // The content is the line as it is in the new file, or in the old one
+        // when it was deleted.
+        let (prefix, hidden) = match line_type {
+            LineType::Inserts => ("+", LineType::Deletes),
+            LineType::Deletes => ("-", LineType::Inserts),
+            _ => (" ", LineType::Deletes),
+        };
+        line.content = line
+            .segments
+            .iter()
+            .filter(|segment| segment.line_type != hidden)
+            .fold(prefix.to_owned(), |content, segment| {
+                content + &segment.content
+            });
+
+        if line_type != LineType::Deletes {
+            line.new_number = self.new_line.to_owned();
+            self.new_line = self.new_line.map(|v| v + 1);
+        }
+        if line_type != LineType::Inserts {
+            line.old_number = self.old_line.to_owned();
+            self.old_line = self.old_line.map(|v| v + 1);
+        }
+        // A changed line counts as deleted and added again.
+        if is_inserted {
+            file.added_lines += 1;
+        }
+        if is_deleted {
+            file.deleted_lines += 1;
+        }
+        line.line_type = Some(line_type);
+
+        block.lines.push(line);
+        self.word_line_start = block.lines.len();
+    }
+
+    fn create_line(&mut self, line: &str) {
+        if !self.parent_lines.is_empty() {
+            self.create_combined_line(line);
+            return;
+        }
+
+        let mut line = Line::new(line.to_owned());
+
+        if line.content.starts_with("+") {
+            self.current_file.as_mut().unwrap().added_lines += 1;
+            line.line_type = Some(LineType::Inserts);
+            line.old_number = None;
+            line.new_number = self.new_line.to_owned();
+            self.new_line.as_mut().map(|v| *v += 1);
+        } else if line.content.starts_with("-") {
+            self.current_file.as_mut().unwrap().deleted_lines += 1;
+            line.line_type = Some(LineType::Deletes);
+            line.old_number = self.old_line.to_owned();
+            self.old_line.as_mut().map(|v| *v += 1);
+            line.new_number = None;
+        } else {
+            line.line_type = Some(LineType::Context);
+            line.old_number = self.old_line.to_owned();
+            self.old_line.as_mut().map(|v| *v += 1);
+            line.new_number = self.new_line.to_owned();
+            self.new_line.as_mut().map(|v| *v += 1);
+        }
+
+        self.current_block.as_mut().unwrap().lines.push(line);
+    }
+
+    /*
+     * A combined diff has one status column per parent. A "-" means the line
+     * is only in that parent, a "+" means the line is in the result but not in
+     * that parent.
+     */
+    fn create_combined_line(&mut self, line: &str) {
+        let parents = self.parent_lines.len();
+
+        // Pad the columns in case trailing whitespace got stripped.
+        let mut content = line.to_owned();
+        while content.len() < parents {
+            content.push(' ');
+        }
+
+        let columns = content.as_bytes()[..parents].to_vec();
+        let is_removed = columns.contains(&b'-');
+        let is_added = columns.contains(&b'+');
+
+        let mut line = Line::new(content);
+        line.parents = columns
+            .iter()
+            .zip(self.parent_lines.iter_mut())
+            .map(|(column, parent_line)| {
+                let (line_type, in_parent) = match column {
+                    b'-' => (LineType::Deletes, true),
+                    b'+' => (LineType::Inserts, false),
+                    _ => (LineType::Context, !is_removed),
+                };
+                let old_number = if in_parent { *parent_line } else { None };
+                if in_parent {
+                    if let Some(v) = parent_line.as_mut() {
+                        *v += 1;
+                    }
+                }
+                ParentLine {
+                    line_type,
+                    old_number,
+                }
+            })
+            .collect();
+        line.old_number = line.parents[0].old_number;
+
+        if is_removed {
+            self.current_file.as_mut().unwrap().deleted_lines += 1;
+            line.line_type = Some(LineType::Deletes);
+        } else {
+            if is_added {
+                self.current_file.as_mut().unwrap().added_lines += 1;
+                line.line_type = Some(LineType::Inserts);
+            } else {
+                line.line_type = Some(LineType::Context);
+            }
+            line.new_number = self.new_line.to_owned();
+            if let Some(v) = self.new_line.as_mut() {
+                *v += 1;
+            }
+        }
+
+        self.current_block.as_mut().unwrap().lines.push(line);
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct Commit {
+    pub sha: Option<String>,
+    pub author: Option<String>,
+    pub date: Option<String>,
+    // First line of the message, without any `[PATCH n/m]` prefix.
+    pub subject: Option<String>,
+    // Rest of the message.
+    pub message: String,
+    pub files: Vec<File>,
+}
+
+impl Commit {
+    fn new() -> Commit {
+        Commit {
+            sha: None,
+            author: None,
+            date: None,
+            subject: None,
+            message: String::new(),
+            files: Vec::new(),
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub enum RangeDiffStatus {
+    Equal,
+    Modified,
+    // Only in the new range.
+    Added,
+    // Only in the old range.
+    Dropped,
+}
+
+// A commit of the old range of a `git range-diff` and its counterpart in the
+// new one.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct RangeDiffPair {
+    pub old_index: Option<usize>,
+    pub old_sha: Option<String>,
+    pub new_index: Option<usize>,
+    pub new_sha: Option<String>,
+    pub status: RangeDiffStatus,
+    pub subject: String,
+    // Diff between the two patches, for modified pairs.
+    pub lines: Vec<RangeDiffLine>,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct RangeDiffLine {
+    // Marker of the diff between the patches, none for its hunk headers.
+    pub outer: Option<LineType>,
+    // Marker of the line in the patch, none for lines outside its hunks.
+    pub inner: Option<LineType>,
+    pub content: String,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct File {
+    pub old_name: Option<String>,
+    pub new_name: Option<String>,
+    pub is_combined: bool,
+    pub is_git_diff: bool,
+    pub language: Option<String>,
+    pub blocks: Vec<Block>,
+    pub added_lines: usize,
+    pub deleted_lines: usize,
+    pub mode: Option<String>,
+    pub old_mode: Option<Vec<String>>,
+    pub new_mode: Option<String>,
+    pub new_file_mode: Option<String>,
+    pub deleted_file_mode: Option<String>,
+    pub is_deleted: bool,
+    pub is_new: bool,
+    pub is_copy: bool,
+    pub is_rename: bool,
+    pub is_binary: bool,
+    pub unchanged_percentage: Option<usize>,
+    pub changed_percentage: Option<usize>,
+    pub checksum_before: Option<Vec<String>>,
+    pub checksum_after: Option<String>,
+    // Sizes in bytes of binary files, when the patch tells them.
+    pub old_size: Option<usize>,
+    pub new_size: Option<usize>,
+    pub binary_patch_kind: Option<BinaryPatchKind>,
+    pub binary_patch: Option<BinaryPatch>,
+    // Revisions named by Subversion, Mercurial and Perforce headers.
+    pub revision_before: Option<String>,
+    pub revision_after: Option<String>,
+    // Subversion property changes.
+    pub properties: Vec<PropertyChange>,
+    pub old_kind: Option<FileKind>,
+    pub new_kind: Option<FileKind>,
+    pub is_type_change: bool,
+    pub submodule: Option<SubmoduleChange>,
+    // Lines are made of words, from `--word-diff=porcelain`.
+    pub is_word_diff: bool,
+}
+
+impl File {
+    fn new() -> File {
+        File {
+            old_name: None,
+            new_name: None,
+            is_combined: false,
+            is_git_diff: false,
+            language: None,
+            blocks: Vec::new(),
+            added_lines: 0,
+            deleted_lines: 0,
+            mode: None,
+            old_mode: None,
+            new_mode: None,
+            new_file_mode: None,
+            deleted_file_mode: None,
+            is_deleted: false,
+            is_new: false,
+            is_copy: false,
+            is_rename: false,
+            is_binary: false,
+            unchanged_percentage: None,
+            changed_percentage: None,
+            checksum_before: None,
+            checksum_after: None,
+            old_size: None,
+            new_size: None,
+            binary_patch_kind: None,
+            binary_patch: None,
+            revision_before: None,
+            revision_after: None,
+            properties: Vec::new(),
+            old_kind: None,
+            new_kind: None,
+            is_type_change: false,
+            submodule: None,
+            is_word_diff: false,
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub enum PropertyAction {
+    Added,
+    Modified,
+    Deleted,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub enum FileKind {
+    Regular,
+    Executable,
+    Symlink,
+    Gitlink,
+}
+
+impl FileKind {
+    /// Kind of a file from its git mode, like `100644`.
+    pub fn from_mode(mode: &str) -> Option<FileKind> {
+        let mode = u32::from_str_radix(mode, 8).ok()?;
+        match mode & 0o170000 {
+            0o100000 if mode & 0o111 != 0 => Some(FileKind::Executable),
+            0o100000 => Some(FileKind::Regular),
+            0o120000 => Some(FileKind::Symlink),
+            0o160000 => Some(FileKind::Gitlink),
+            _ => None,
+        }
+    }
+}
+
+/// Commits a submodule (gitlink) points to before and after the change.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct SubmoduleChange {
+    pub old_commit: Option<String>,
+    pub new_commit: Option<String>,
+    // The submodule has uncommitted changes (`-dirty`).
+    pub is_dirty: bool,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct PropertyChange {
+    pub name: String,
+    pub action: PropertyAction,
+    pub old_value: Option<String>,
+    pub new_value: Option<String>,
+}
+
+#[derive(Clone, Debug, Hash, Serialize, Deserialize)]
+pub struct Line {
+    pub content: String,
+    pub line_type: Option<LineType>,
+    pub old_number: Option<usize>,
+    pub new_number: Option<usize>,
+    pub no_newline_at_eof: bool,
+    // Per-parent columns of a combined diff, empty otherwise.
+    pub parents: Vec<ParentLine>,
+    // Words of a line of a `--word-diff=porcelain` diff, empty otherwise.
+    pub segments: Vec<Segment>,
+}
+
+impl Line {
+    fn new(line: String) -> Line {
+        Line {
+            content: line,
+            line_type: None,
+            old_number: None,
+            new_number: None,
+            no_newline_at_eof: false,
+            parents: Vec::new(),
+            segments: Vec::new(),
+        }
+    }
+}
+
+#[derive(Clone, Debug, Hash, Serialize, Deserialize)]
+pub struct Segment {
+    pub line_type: LineType,
+    pub content: String,
+}
+
+#[derive(Clone, Debug, Hash, Serialize, Deserialize)]
+pub struct ParentLine {
+    pub line_type: LineType,
+    pub old_number: Option<usize>,
+}
+
+#[derive(Clone, Debug, Hash, PartialEq, Serialize, Deserialize)]
+pub enum LineType {
+    Inserts,
+    Deletes,
+    Context,
+    //Info,
+    InsertChanges,
+    DeleteChanges,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub enum Severity {
+    Warning,
+    Error,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub enum DiagnosticKind {
+    InvalidHunkHeader,
+    InvalidNumber,
+    TruncatedInput,
+    HunkLengthMismatch,
+    InvalidBinaryPatch,
+    InvalidFileHeader,
+    ReadError,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct Diagnostic {
+    // 1-based line number in the input.
+    pub line: usize,
+    pub severity: Severity,
+    pub kind: DiagnosticKind,
+    pub message: String,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub enum BinaryPatchKind {
+    Literal,
+    Delta,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct BinaryHunk {
+    pub kind: BinaryPatchKind,
+    // Size of the inflated data.
+    pub size: usize,
+    pub data: Vec<u8>,
+}
+
+impl BinaryHunk {
+    /// Applies the hunk to `base`, which only matters for deltas.
+    pub fn apply(&self, base: &[u8]) -> Option<Vec<u8>> {
+        match self.kind {
+            BinaryPatchKind::Literal => Some(self.data.to_owned()),
+            BinaryPatchKind::Delta => binary::apply_delta(base, &self.data),
+        }
+    }
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct BinaryPatch {
+    // Turns the old content into the new one.
+    pub forward: BinaryHunk,
+    // Turns the new content back into the old one.
+    pub reverse: Option<BinaryHunk>,
+}
+
+impl BinaryPatch {
+    pub fn apply(&self, old: &[u8]) -> Option<Vec<u8>> {
+        self.forward.apply(old)
+    }
+
+    pub fn revert(&self, new: &[u8]) -> Option<Vec<u8>> {
+        self.reverse.as_ref().and_then(|hunk| hunk.apply(new))
+    }
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct Block {
+    pub lines: Vec<Line>,
+    pub header: Option<String>,
+    pub old_start: Option<usize>,
+    pub old_count: Option<usize>,
+    pub new_start: Option<usize>,
+    pub new_count: Option<usize>,
+    // Function context git prints after the hunk range.
+    pub section_heading: Option<String>,
+    // Ranges of every parent of a combined diff, empty otherwise.
+    pub parent_ranges: Vec<ParentRange>,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct ParentRange {
+    pub start: usize,
+    pub count: usize,
+}
+
+impl Block {
+    fn new() -> Block {
+        Block {
+            lines: Vec::new(),
+            header: None,
+            old_start: None,
+            old_count: None,
+            new_start: None,
+            new_count: None,
+            section_heading: None,
+            parent_ranges: Vec::new(),
+        }
+    }
+
+    fn line_counts(&self) -> (usize, usize) {
+        if !self.parent_ranges.is_empty() {
+            let new = self
+                .lines
+                .iter()
+                .filter(|line| line.new_number.is_some())
+                .count();
+            return (self.parent_line_count(0), new);
+        }
+
+        self.lines
+            .iter()
+            .fold((0, 0), |(old, new), line| match line.line_type {
+                Some(LineType::Deletes) | Some(LineType::DeleteChanges) => (old + 1, new),
+                Some(LineType::Inserts) | Some(LineType::InsertChanges) => (old, new + 1),
+                _ => (old + 1, new + 1),
+            })
+    }
+
+    // Number of lines that exist in the given parent of a combined diff.
+    fn parent_line_count(&self, parent: usize) -> usize {
+        self.lines
+            .iter()
+            .filter(|line| {
+                line.parents
+                    .get(parent)
+                    .is_some_and(|column| column.old_number.is_some())
+            })
+            .count()
+    }
+}
+
+fn get_section_heading(heading: &str) -> Option<String> {
+    let heading = heading.trim();
+    if heading.is_empty() {
+        None
+    } else {
+        Some(heading.to_owned())
+    }
+}
+
+// Splits the `(revision 123)` or `(working copy)` Subversion puts after a tab
+// from a `---`/`+++` line. Anything else after the tab is a timestamp.
+fn split_revision(line: &str) -> (&str, Option<String>) {
+    lazy_static! {
+        static ref REVISION: Regex = Regex::new(r#"^\((?:revision )?([^()]*)\)$"#).unwrap();
+    }
+
+    match line.find('\t') {
+        Some(index) => (
+            &line[..index],
+            REVISION
+                .captures(&line[index + 1..])
+                .map(|captures| captures[1].to_owned()),
+        ),
+        _ => (line, None),
+    }
+}
+
+fn set_file_kinds(file: &mut File) {
+    let old_mode = file
+        .old_mode
+        .as_ref()
+        .and_then(|modes| modes.first())
+        .or(file.deleted_file_mode.as_ref())
+        .or(file.mode.as_ref());
+    let new_mode = file
+        .new_mode
+        .as_ref()
+        .or(file.new_file_mode.as_ref())
+        .or(file.mode.as_ref());
+
+    if !file.is_new {
+        file.old_kind = old_mode.and_then(|mode| FileKind::from_mode(mode));
+    }
+    if !file.is_deleted {
+        file.new_kind = new_mode.and_then(|mode| FileKind::from_mode(mode));
+    }
+
+    // A type change shown as one file, as with `git diff -B`.
+    file.is_type_change = is_type_change(file.old_kind, file.new_kind);
+}
+
+// Changing only the executable bit is a mode change, not a type change.
+fn is_type_change(old_kind: Option<FileKind>, new_kind: Option<FileKind>) -> bool {
+    let is_file = |kind| kind == Some(FileKind::Regular) || kind == Some(FileKind::Executable);
+    old_kind.is_some()
+        && new_kind.is_some()
+        && old_kind != new_kind
+        && !(is_file(old_kind) && is_file(new_kind))
+}
+
+// The diff of a gitlink is a single `Subproject commit <sha>` line per side.
+fn get_submodule_change(file: &File) -> Option<SubmoduleChange> {
+    lazy_static! {
+        static ref SUBPROJECT_COMMIT: Regex =
+            Regex::new(r#"^[-+ ]Subproject commit ([0-9a-f]+)(-dirty)?$"#).unwrap();
+    }
+
+    if file.old_kind != Some(FileKind::Gitlink) && file.new_kind != Some(FileKind::Gitlink) {
+        return None;
+    }
+
+    let mut change = SubmoduleChange {
+        old_commit: None,
+        new_commit: None,
+        is_dirty: false,
+    };
+    for line in file.blocks.iter().flat_map(|block| block.lines.iter()) {
+        let captures = SUBPROJECT_COMMIT.captures(&line.content)?;
+        let commit = Some(captures[1].to_owned());
+        if line.line_type != Some(LineType::Inserts) {
+            change.old_commit = commit.clone();
+        }
+        if line.line_type != Some(LineType::Deletes) {
+            change.new_commit = commit;
+            change.is_dirty = captures.get(2).is_some();
+        }
+    }
+
+    Some(change)
+}
+
+// Context diff headers separate the file name from the timestamp with a tab.
+fn get_context_filename(line: &str) -> Option<String> {
+    let name = line[4..].split('\t').next().unwrap_or("");
+    get_filename(None, name)
+}
+
+fn get_src_filename(line: &str) -> Option<String> {
+    get_filename(Some("--- "), line)
+}
+
+fn get_dst_filename(line: &str) -> Option<String> {
+    get_filename(Some("+++ "), line)
+}
+
+fn get_filename(line_prefix: Option<&str>, line: &str) -> Option<String> {
+    lazy_static! {
+        static ref DATE: Regex =
+            Regex::new(r#"\s+\d{4}-\d{2}-\d{2} \d{2}:\d{2}:\d{2}(?:\.\d+)? [-+]\d{4}.*$"#).unwrap();
+    }
+
+    let name = match line_prefix {
+        Some(line_prefix) if line.starts_with(line_prefix) => &line[line_prefix.len()..],
+        Some(_) => return None,
+        _ => line,
+    };
+    if name.is_empty() {
+        return None;
+    }
+
+    // Quoted names are exact, anything after the closing quote is not part of them.
+    match quote::unquote(name) {
+        Some((filename, _)) => Some(filename),
+        _ => Some(DATE.replace(name, "").to_string()),
+    }
+}
+
+// Prefixes of the two paths of a `diff --git` line. Both paths start with
+// their prefix, so they only differ in it unless the file was renamed.
+// Without one, like with `--no-prefix`, both prefixes are empty.
+fn detect_prefixes(old_name: &str, new_name: &str) -> (String, String) {
+    lazy_static! {
+        static ref MNEMONIC_PREFIXES: Vec<&'static str> =
+            vec!["a/", "b/", "i/", "w/", "c/", "o/", "1/", "2/"];
+    }
+
+    if let (Some((old_prefix, old_path)), Some((new_prefix, new_path))) = (
+        split_first_component(old_name),
+        split_first_component(new_name),
+    ) {
+        let is_mnemonic =
+            MNEMONIC_PREFIXES.contains(&old_prefix) && MNEMONIC_PREFIXES.contains(&new_prefix);
+        if old_prefix != new_prefix && (old_path == new_path || is_mnemonic) {
+            return (old_prefix.to_owned(), new_prefix.to_owned());
+        }
+    }
+
+    (String::new(), String::new())
+}
+
+fn split_first_component(name: &str) -> Option<(&str, &str)> {
+    name.find('/').map(|i| name.split_at(i + 1))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::cell::Cell;
+    use std::io::{self, BufRead, Read};
+    use std::rc::Rc;
+
+    use super::*;
+
+    static TWO_FILES: &str = "diff --git a/one.txt b/one.txt
+index 1111111..2222222 100644
+--- a/one.txt
++++ b/one.txt
+@@ -1,2 +1,2 @@
+ same
+-old
++new
+diff --git a/two.txt b/two.txt
+index 3333333..4444444 100644
+--- a/two.txt
++++ b/two.txt
+@@ -1 +1,2 @@
+ first
++second
+";
+
+    // Serves its input a line at a time and tells how much was read, then
+    // fails if `error` is set.
+    struct LineReader {
+        lines: Vec<Vec<u8>>,
+        next: usize,
+        read: Rc<Cell<usize>>,
+        error: bool,
+    }
+
+    impl LineReader {
+        fn new(input: &str, read: Rc<Cell<usize>>, error: bool) -> LineReader {
+            LineReader {
+                lines: input
+                    .split_inclusive('\n')
+                    .map(|line| line.as_bytes().to_vec())
+                    .collect(),
+                next: 0,
+                read,
+                error,
+            }
+        }
+    }
+
+    impl Read for LineReader {
+        fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+            let available = self.fill_buf()?;
+            let len = available.len().min(buf.len());
+            buf[..len].copy_from_slice(&available[..len]);
+            self.consume(len);
+            Ok(len)
+        }
+    }
+
+    impl BufRead for LineReader {
+        fn fill_buf(&mut self) -> io::Result<&[u8]> {
+            match self.lines.get(self.next) {
+                Some(line) => Ok(line),
+                None if self.error => Err(io::Error::other("disk on fire")),
+                None => Ok(&[]),
+            }
+        }
+
+        fn consume(&mut self, amount: usize) {
+            if let Some(line) = self.lines.get_mut(self.next) {
+                line.drain(..amount);
+                if line.is_empty() {
+                    self.next += 1;
+                    self.read.set(self.read.get() + 1);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn reader_yields_files_before_the_input_ends() {
+        let read = Rc::new(Cell::new(0));
+        let mut reader = read_diff(LineReader::new(TWO_FILES, read.clone(), false));
+
+        let first = reader.next().unwrap();
+        assert_eq!(first.new_name.as_deref(), Some("one.txt"));
+        assert!(read.get() < TWO_FILES.lines().count());
+
+        let second = reader.next().unwrap();
+        assert_eq!(second.new_name.as_deref(), Some("two.txt"));
+        assert_eq!(second.added_lines, 1);
+        assert!(reader.next().is_none());
+        assert!(reader.take_error().is_none());
+    }
+
+    #[test]
+    fn reader_matches_parse_diff() {
+        let streamed = read_diff(TWO_FILES.as_bytes()).collect::<Vec<File>>();
+        let parsed = parse_diff(TWO_FILES);
+        assert_eq!(
+            serde_json::to_string(&streamed).unwrap(),
+            serde_json::to_string(&parsed).unwrap()
+        );
+    }
+
+    #[test]
+    fn reader_accepts_crlf_line_endings() {
+        let files = parse_diff(&TWO_FILES.replace('\n', "\r\n"));
+        assert_eq!(files.len(), 2);
+        assert_eq!(files[0].blocks[0].lines[2].content, "+new");
+    }
+
+    #[test]
+    fn reader_reports_read_errors() {
+        let input = TWO_FILES.lines().take(12).collect::<Vec<&str>>().join("\n") + "\n";
+        let mut reader = read_diff(LineReader::new(&input, Rc::new(Cell::new(0)), true));
+
+        let files = reader.by_ref().collect::<Vec<File>>();
+        assert_eq!(files.len(), 2);
+        assert_eq!(files[1].blocks.len(), 0);
+
+        let error = reader.take_error().unwrap();
+        assert_eq!(error.to_string(), "disk on fire");
+        let diagnostic = reader
+            .diagnostics()
+            .iter()
+            .find(|diagnostic| diagnostic.kind == DiagnosticKind::ReadError)
+            .unwrap();
+        assert_eq!(diagnostic.severity, Severity::Error);
+        assert_eq!(diagnostic.line, 13);
+    }
+
+    fn diagnostic_kinds(input: &str) -> Vec<DiagnosticKind> {
+        match try_parse_diff(input) {
+            Ok(_) => Vec::new(),
+            Err(diagnostics) => diagnostics
+                .into_iter()
+                .map(|diagnostic| diagnostic.kind)
+                .collect(),
+        }
+    }
+
+    #[test]
+    fn try_parse_diff_accepts_valid_input() {
+        let files = try_parse_diff(TWO_FILES).unwrap();
+        assert_eq!(files.len(), 2);
+    }
+
+    #[test]
+    fn try_parse_diff_rejects_invalid_hunk_headers() {
+        let input = "--- a/x\n+++ b/x\n@@ -a,1 +1 @@\n-old\n+new\n";
+        let diagnostics = try_parse_diff(input).unwrap_err();
+        assert_eq!(diagnostics[0].kind, DiagnosticKind::InvalidHunkHeader);
+        assert_eq!(diagnostics[0].severity, Severity::Error);
+        assert_eq!(diagnostics[0].line, 3);
+    }
+
+    #[test]
+    fn try_parse_diff_rejects_invalid_numbers() {
+        let input = "diff --git a/x b/y\nsimilarity index 99999999999999999999999%\nrename from x\nrename to y\n";
+        assert_eq!(diagnostic_kinds(input), vec![DiagnosticKind::InvalidNumber]);
+    }
+
+    #[test]
+    fn try_parse_diff_rejects_hunk_length_mismatches() {
+        let input = "--- a/x\n+++ b/x\n@@ -1,3 +1,3 @@\n-old\n+new\n@@ -10 +10 @@\n-a\n+b\n";
+        let diagnostics = try_parse_diff(input).unwrap_err();
+        assert_eq!(diagnostics.len(), 1);
+        assert_eq!(diagnostics[0].kind, DiagnosticKind::HunkLengthMismatch);
+        // Reported on the header of the hunk.
+        assert_eq!(diagnostics[0].line, 3);
+    }
+
+    #[test]
+    fn try_parse_diff_rejects_truncated_input() {
+        let input = "diff --git a/x b/x\n--- a/x\n+++ b/x\n";
+        assert_eq!(
+            diagnostic_kinds(input),
+            vec![DiagnosticKind::TruncatedInput]
+        );
+    }
+
+    #[test]
+    fn malformed_input_does_not_panic() {
+        let inputs = [
+            "",
+            "\n\n\n",
+            "@@ -1 +1 @@\n",
+            "+++ b/x\n",
+            "--- a/x\n",
+            "diff --git\n@@\n+",
+            "diff --cc x\n@@@ -1 -1 +1 @@@\n+\n--\n",
+            "diff --git a/x b/x\nGIT binary patch\nliteral 5\nzzzzz\n\n",
+            "*** x\n--- x\n***************\n*** 1,9 ****\n--- 1 ----\n",
+            "1,2c3\n< a\n---\n",
+            "Index: x\nProperty changes on: x\nAdded: p\n",
+            "==== x#1 - y ====\n",
+            "--- a/x\n+++ b/x\n@@ -1 +1 @@\n-a\n~\n~\n",
+        ];
+        for input in inputs.iter() {
+            let _ = try_parse_diff(input);
+            let _ = parse_diff(&input.replace('\n', "\r"));
+        }
+    }
+
+    fn headers(file: &File) -> Vec<&str> {
+        file.blocks
+            .iter()
+            .filter_map(|block| block.header.as_deref())
+            .collect()
+    }
+
+    #[test]
+    fn parses_normal_diffs() {
+        let files = parse_diff("diff c1 c2\n2c2\n< b\n---\n> B\n7a8\n> h\n0a1\n> x\n");
+        assert_eq!(files.len(), 1);
+        assert_eq!(files[0].old_name.as_deref(), Some("c1"));
+        assert_eq!(
+            headers(&files[0]),
+            vec!["@@ -2,1 +2,1 @@", "@@ -7,0 +8,1 @@", "@@ -0,0 +1,1 @@"]
+        );
+        assert_eq!(files[0].blocks[0].lines[0].content, "-b");
+        assert_eq!(files[0].blocks[0].lines[1].content, "+B");
+        assert_eq!(files[0].added_lines, 3);
+        assert_eq!(files[0].deleted_lines, 1);
+    }
+
+    #[test]
+    fn normal_commands_need_their_lines() {
+        let files = parse_diff("Fixes 12a13 and 2d1\n12a13\nsee above\n");
+        assert!(files.is_empty());
+    }
+
+    #[test]
+    fn normal_commands_do_not_start_files_after_unified_headers() {
+        let input = "diff --git a/x b/x
+index 1111111..2222222 100644
+--- a/x
++++ b/x
+@@ -1,2 +1,2 @@
+ 12a13
+-> old
++> new
+";
+        let files = parse_diff(input);
+        assert_eq!(files.len(), 1);
+        assert_eq!(headers(&files[0]), vec!["@@ -1,2 +1,2 @@"]);
+        assert_eq!(files[0].blocks[0].lines[0].content, " 12a13");
+
+        let files = parse_diff("--- a/x\n+++ b/x\n1a2\n> x\n");
+        assert!(files.iter().all(|file| file.blocks.is_empty()));
+    }
+
+    #[test]
+    fn perforce_headers_with_an_empty_path_are_diagnosed() {
+        let files = parse_diff("====  - x ====\n");
+        assert!(files.is_empty());
+        assert_eq!(
+            diagnostic_kinds("====  - x ====\n"),
+            vec![DiagnosticKind::InvalidFileHeader]
+        );
+    }
+
+    #[test]
+    fn vcs_headers_in_a_preamble_do_not_start_files() {
+        let input = "Index: see the notes below
+# HG changeset patch is how hg export starts
+# Node ID 1234
+
+diff --git a/x b/x
+--- a/x
++++ b/x
+@@ -1 +1 @@
+-a
++b
+";
+        let files = parse_diff(input);
+        assert_eq!(files.len(), 1);
+        assert_eq!(files[0].new_name.as_deref(), Some("x"));
+        assert_eq!(files[0].revision_before, None);
+    }
+
+    #[test]
+    fn unified_headers_end_subversion_properties() {
+        let input = "Index: dir
+===================================================================
+
+Property changes on: dir
+___________________________________________________________________
+Added: svn:ignore
+## -0,0 +1 ##
++*.o
+--- a/x
++++ b/x
+@@ -1 +1 @@
+-a
++b
+";
+        let files = parse_diff(input);
+        assert_eq!(files.len(), 2);
+        assert_eq!(files[0].properties.len(), 1);
+        assert_eq!(files[0].properties[0].new_value.as_deref(), Some("*.o"));
+        assert_eq!(files[1].new_name.as_deref(), Some("x"));
+        assert_eq!(files[1].added_lines, 1);
+    }
+
+    fn names(input: &str) -> Vec<(Option<String>, Option<String>)> {
+        parse_diff(input)
+            .into_iter()
+            .map(|file| (file.old_name, file.new_name))
+            .collect()
+    }
+
+    #[test]
+    fn detects_prefixes_from_git_diff_lines() {
+        let input = "diff --git left/x right/x
+index 1111111..2222222 100644
+--- left/x
++++ right/x
+@@ -1 +1 @@
+-a
++b
+diff --git a/foo/x b/bar/x
+similarity index 90%
+rename from foo/x
+rename to bar/x
+";
+        assert_eq!(
+            names(input),
+            vec![
+                (Some("x".to_owned()), Some("x".to_owned())),
+                (Some("foo/x".to_owned()), Some("bar/x".to_owned())),
+            ]
+        );
+    }
+
+    #[test]
+    fn keeps_unprefixed_cross_directory_renames() {
+        let input = "diff --git foo/x bar/x
+similarity index 90%
+rename from foo/x
+rename to bar/x
+index 1111111..2222222 100644
+--- foo/x
++++ bar/x
+@@ -1 +1 @@
+-a
++b
+diff --git foo/y bar/y
+similarity index 100%
+rename from foo/y
+rename to bar/y
+";
+        assert_eq!(
+            names(input),
+            vec![
+                (Some("foo/x".to_owned()), Some("bar/x".to_owned())),
+                (Some("foo/y".to_owned()), Some("bar/y".to_owned())),
+            ]
+        );
+    }
+
+    #[test]
+    fn detects_script_languages_from_their_shebang() {
+        let diff = |name: &str| {
+            format!(
+                "diff --git a/{0} b/{0}\nnew file mode 100755\n--- /dev/null\n+++ b/{0}\n@@ -0,0 +1,2 @@\n+#!/usr/bin/env python3\n+b\n",
+                name
+            )
+        };
+        let language = |name: &str| parse_diff(&diff(name))[0].language.to_owned();
+        assert_eq!(language("bin/run").as_deref(), Some("python"));
+        assert_eq!(language("run.rb").as_deref(), Some("ruby"));
+        assert_eq!(language("run.unknown"), None);
+
+        // Only new files are looked at, whatever the first line of an
+        // existing one is.
+        let files = parse_diff(
+            "diff --git a/bin/run b/bin/run\n--- a/bin/run\n+++ b/bin/run\n@@ -1,2 +1,2 @@\n #!/usr/bin/env python3\n-a\n+b\n",
+        );
+        assert_eq!(files[0].language, None);
+    }
+
+    #[test]
+    fn word_line_ends_need_a_file() {
+        let mut state = ParseState::new();
+        state.current_block = Some(Block::new());
+        state.end_word_line();
+        assert!(state.current_file.is_none());
+
+        let files = parse_diff("--- a/x\n+++ b/x\n@@ -1 +1 @@\n-a\n+b\n~\n");
+        assert_eq!(files.len(), 1);
+        assert_eq!(files[0].blocks[0].lines.len(), 1);
+        assert_eq!(files[0].blocks[0].lines[0].content, " b");
+    }
+
+    #[test]
+    fn stores_hunk_ranges_and_section_headings() {
+        let files = parse_diff(
+            "--- a/x\n+++ b/x\n@@ -10,3 +12,4 @@ fn main() {\n a\n-b\n+B\n+C\n c\n@@ -5 +5 @@\n-e\n+E\n",
+        );
+        let blocks = &files[0].blocks;
+        assert_eq!(
+            (
+                blocks[0].old_start,
+                blocks[0].old_count,
+                blocks[0].new_start,
+                blocks[0].new_count
+            ),
+            (Some(10), Some(3), Some(12), Some(4))
+        );
+        assert_eq!(blocks[0].section_heading.as_deref(), Some("fn main() {"));
+        // The count is one when it is left out.
+        assert_eq!(
+            (
+                blocks[1].old_start,
+                blocks[1].old_count,
+                blocks[1].new_start,
+                blocks[1].new_count
+            ),
+            (Some(5), Some(1), Some(5), Some(1))
+        );
+        assert_eq!(blocks[1].section_heading, None);
+        assert_eq!(blocks[1].lines[0].old_number, Some(5));
+    }
+
+    #[test]
+    fn diagnoses_hunks_longer_than_their_header() {
+        let input = "--- a/x\n+++ b/x\n@@ -1 +1 @@\n-a\n-b\n+c\n";
+        let diagnostics = try_parse_diff(input).unwrap_err();
+        assert_eq!(diagnostics[0].kind, DiagnosticKind::HunkLengthMismatch);
+        assert_eq!(diagnostics[0].line, 3);
+
+        assert!(try_parse_diff("--- a/x\n+++ b/x\n@@ -1,2 +1 @@\n-a\n b\n").is_ok());
+    }
+
+    #[test]
+    fn attaches_missing_newlines_to_their_side() {
+        let files = parse_diff(
+            "--- a/x\n+++ b/x\n@@ -1,2 +1,2 @@\n a\n-b\n\\ No newline at end of file\n+B\n",
+        );
+        let lines = &files[0].blocks[0].lines;
+        assert_eq!(lines.len(), 3);
+        assert!(!lines[0].no_newline_at_eof);
+        assert!(lines[1].no_newline_at_eof);
+        assert!(!lines[2].no_newline_at_eof);
+
+        let files = parse_diff(
+            "--- a/x\n+++ b/x\n@@ -1,2 +1,2 @@\n a\n-b\n+B\n\\ No newline at end of file\n",
+        );
+        let lines = &files[0].blocks[0].lines;
+        assert!(!lines[1].no_newline_at_eof);
+        assert!(lines[2].no_newline_at_eof);
+
+        let json = serde_json::to_value(&files[0]).unwrap();
+        assert_eq!(json["blocks"][0]["lines"][2]["no_newline_at_eof"], true);
+        assert_eq!(json["blocks"][0]["lines"][1]["no_newline_at_eof"], false);
+    }
+
+    #[test]
+    fn models_combined_lines_per_parent() {
+        let files = try_parse_diff(
+            "diff --cc x
+index 1111111,2222222..3333333
+--- a/x
++++ b/x
+@@@ -1,3 -1,3 +1,3 @@@
+  a
+- b
+ -c
+++d
+  e
+",
+        )
+        .unwrap();
+        let block = &files[0].blocks[0];
+        let ranges = block
+            .parent_ranges
+            .iter()
+            .map(|range| (range.start, range.count))
+            .collect::<Vec<_>>();
+        assert_eq!(ranges, vec![(1, 3), (1, 3)]);
+        assert_eq!(crate::printers::utils::get_prefix_size(&files[0]), 2);
+
+        let parents = |i: usize| {
+            block.lines[i]
+                .parents
+                .iter()
+                .map(|parent| (parent.line_type.clone(), parent.old_number))
+                .collect::<Vec<_>>()
+        };
+        use LineType::*;
+        assert_eq!(parents(0), vec![(Context, Some(1)), (Context, Some(1))]);
+        assert_eq!(parents(1), vec![(Deletes, Some(2)), (Context, None)]);
+        assert_eq!(parents(2), vec![(Context, None), (Deletes, Some(2))]);
+        assert_eq!(parents(3), vec![(Inserts, None), (Inserts, None)]);
+        assert_eq!(parents(4), vec![(Context, Some(3)), (Context, Some(3))]);
+        let numbers = block
+            .lines
+            .iter()
+            .map(|line| (line.line_type.clone(), line.old_number, line.new_number))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            numbers,
+            vec![
+                (Some(Context), Some(1), Some(1)),
+                (Some(Deletes), Some(2), None),
+                (Some(Deletes), None, None),
+                (Some(Inserts), None, Some(2)),
+                (Some(Context), Some(3), Some(3)),
+            ]
+        );
+    }
+
+    #[test]
+    fn models_octopus_merges() {
+        let files = try_parse_diff(
+            "diff --cc y
+--- a/y
++++ b/y
+@@@@ -1,2 -1,2 -1,2 +1,2 @@@@
+   a
+---b
++++B
+",
+        )
+        .unwrap();
+        let block = &files[0].blocks[0];
+        assert_eq!(block.parent_ranges.len(), 3);
+        assert_eq!(crate::printers::utils::get_prefix_size(&files[0]), 3);
+        assert!(block.lines[1]
+            .parents
+            .iter()
+            .all(|parent| parent.line_type == LineType::Deletes && parent.old_number == Some(2)));
+        assert_eq!(block.lines[2].new_number, Some(2));
+    }
+
+    #[test]
+    fn tells_file_kinds_from_their_mode() {
+        assert_eq!(FileKind::from_mode("100644"), Some(FileKind::Regular));
+        assert_eq!(FileKind::from_mode("100755"), Some(FileKind::Executable));
+        assert_eq!(FileKind::from_mode("120000"), Some(FileKind::Symlink));
+        assert_eq!(FileKind::from_mode("160000"), Some(FileKind::Gitlink));
+        assert_eq!(FileKind::from_mode("040000"), None);
+        assert_eq!(FileKind::from_mode("abc"), None);
+
+        let files = parse_diff("diff --git a/x b/x\nold mode 100644\nnew mode 100755\n");
+        assert_eq!(files[0].old_kind, Some(FileKind::Regular));
+        assert_eq!(files[0].new_kind, Some(FileKind::Executable));
+        assert!(!files[0].is_type_change);
+    }
+
+    #[test]
+    fn marks_type_changes_split_into_two_files() {
+        let files = parse_diff(
+            "diff --git a/l b/l
+deleted file mode 100644
+index 1111111..0000000
+--- a/l
++++ /dev/null
+@@ -1 +0,0 @@
+-text
+diff --git a/l b/l
+new file mode 120000
+index 0000000..2222222
+--- /dev/null
++++ b/l
+@@ -0,0 +1 @@
++target
+\\ No newline at end of file
+diff --git a/m b/m
+deleted file mode 100644
+index 1111111..0000000
+--- a/m
++++ /dev/null
+@@ -1 +0,0 @@
+-text
+",
+        );
+        assert_eq!(files.len(), 3);
+        for file in &files[..2] {
+            assert!(file.is_type_change);
+            assert_eq!(file.old_kind, Some(FileKind::Regular));
+            assert_eq!(file.new_kind, Some(FileKind::Symlink));
+        }
+        assert!(files[0].is_deleted);
+        assert!(files[1].is_new);
+
+        // The last deleted file is still written out once the input ends.
+        assert!(files[2].is_deleted);
+        assert!(!files[2].is_type_change);
+        assert_eq!(files[2].new_kind, None);
+    }
+
+    #[test]
+    fn reads_submodule_commits() {
+        let files = parse_diff(
+            "diff --git a/sub b/sub
+index 1111111..2222222 160000
+--- a/sub
++++ b/sub
+@@ -1 +1 @@
+-Subproject commit 1111111111111111111111111111111111111111
++Subproject commit 2222222222222222222222222222222222222222-dirty
+",
+        );
+        assert_eq!(files[0].new_kind, Some(FileKind::Gitlink));
+        let submodule = files[0].submodule.as_ref().unwrap();
+        assert_eq!(
+            submodule.old_commit.as_deref(),
+            Some("1111111111111111111111111111111111111111")
+        );
+        assert_eq!(
+            submodule.new_commit.as_deref(),
+            Some("2222222222222222222222222222222222222222")
+        );
+        assert!(submodule.is_dirty);
+
+        // Any other line means it is not a plain gitlink diff.
+        let files = parse_diff(
+            "diff --git a/sub b/sub\nindex 1111111..2222222 160000\n--- a/sub\n+++ b/sub\n@@ -1 +1 @@\n-a\n+b\n",
+        );
+        assert!(files[0].submodule.is_none());
+    }
+
+    #[test]
+    fn reads_word_diff_porcelain_lines() {
+        let files = parse_diff(
+            "diff --git a/x b/x
+index 1111111..2222222 100644
+--- a/x
++++ b/x
+@@ -1,3 +1,3 @@
+ let 
+-a<b
++c
+  = 1;
+~
+-gone
+~
++new
+~
+ same
+~
+",
+        );
+        let file = &files[0];
+        assert!(file.is_word_diff);
+        // The changed line counts on both sides.
+        assert_eq!((file.added_lines, file.deleted_lines), (2, 2));
+
+        let lines = &file.blocks[0].lines;
+        let summary = lines
+            .iter()
+            .map(|line| {
+                (
+                    line.line_type.clone(),
+                    line.content.as_str(),
+                    line.old_number,
+                    line.new_number,
+                )
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(
+            summary,
+            vec![
+                (Some(LineType::Context), " let c = 1;", Some(1), Some(1)),
+                (Some(LineType::Deletes), "-gone", Some(2), None),
+                (Some(LineType::Inserts), "+new", None, Some(2)),
+                (Some(LineType::Context), " same", Some(3), Some(3)),
+            ]
+        );
+
+        let segments = lines[0]
+            .segments
+            .iter()
+            .map(|segment| (segment.line_type.clone(), segment.content.as_str()))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            segments,
+            vec![
+                (LineType::Context, "let "),
+                (LineType::Deletes, "a<b"),
+                (LineType::Inserts, "c"),
+                (LineType::Context, " = 1;"),
+            ]
+        );
+        assert_eq!(lines[1].segments.len(), 1);
+    }
+}
@@ -0,0 +1,216 @@
+/*
+ * Git quotes paths with unusual characters C-style: `"a/h\303\251llo.txt"`.
+ * Octal escapes are raw bytes, so the decoded bytes are UTF-8 again in
+ * practice. The byte variants keep names that are not.
+ */
+
+/// Decodes a path that may be quoted, replacing bytes that are not UTF-8.
+pub fn unquote_path(path: &str) -> String {
+    String::from_utf8_lossy(&unquote_path_bytes(path)).into_owned()
+}
+
+/// Decodes a path that may be quoted into the bytes of the file name.
+pub fn unquote_path_bytes(path: &str) -> Vec<u8> {
+    match unquote_bytes(path) {
+        Some((name, "")) => name,
+        _ => path.as_bytes().to_vec(),
+    }
+}
+
+/// Like `unquote_bytes`, with the string decoded as UTF-8 and bytes that are
+/// not replaced.
+pub fn unquote(value: &str) -> Option<(String, &str)> {
+    unquote_bytes(value)
+        .map(|(decoded, rest)| (String::from_utf8_lossy(&decoded).into_owned(), rest))
+}
+
+/// Decodes the quoted string at the start of `value`, returning its bytes and
+/// the text after the closing quote.
+pub fn unquote_bytes(value: &str) -> Option<(Vec<u8>, &str)> {
+    let bytes = value.as_bytes();
+    if bytes.first() != Some(&b'"') {
+        return None;
+    }
+
+    let mut decoded = Vec::new();
+    let mut i = 1;
+    while i < bytes.len() {
+        match bytes[i] {
+            b'"' => return Some((decoded, &value[i + 1..])),
+            b'\\' => {
+                let escaped = *bytes.get(i + 1)?;
+                i += 2;
+                match escaped {
+                    b'a' => decoded.push(0x07),
+                    b'b' => decoded.push(0x08),
+                    b'f' => decoded.push(0x0c),
+                    b'n' => decoded.push(b'\n'),
+                    b'r' => decoded.push(b'\r'),
+                    b't' => decoded.push(b'\t'),
+                    b'v' => decoded.push(0x0b),
+                    b'0'..=b'3' => {
+                        let octal = std::str::from_utf8(bytes.get(i - 1..i + 2)?).ok()?;
+                        decoded.push(u8::from_str_radix(octal, 8).ok()?);
+                        i += 2;
+                    }
+                    other => decoded.push(other),
+                }
+            }
+            byte => {
+                decoded.push(byte);
+                i += 1;
+            }
+        }
+    }
+
+    None
+}
+
+/// Quotes a path the way git does when it contains control characters,
+/// quotes, backslashes or non-ASCII bytes.
+pub fn quote_path(path: &str) -> String {
+    let needs_quotes = path
+        .bytes()
+        .any(|byte| !(0x20..0x7f).contains(&byte) || byte == b'"' || byte == b'\\');
+    if !needs_quotes {
+        return path.to_owned();
+    }
+
+    let mut quoted = String::from("\"");
+    for byte in path.bytes() {
+        match byte {
+            0x07 => quoted.push_str("\\a"),
+            0x08 => quoted.push_str("\\b"),
+            0x0c => quoted.push_str("\\f"),
+            b'\n' => quoted.push_str("\\n"),
+            b'\r' => quoted.push_str("\\r"),
+            b'\t' => quoted.push_str("\\t"),
+            0x0b => quoted.push_str("\\v"),
+            b'"' => quoted.push_str("\\\""),
+            b'\\' => quoted.push_str("\\\\"),
+            byte if !(0x20..0x7f).contains(&byte) => quoted.push_str(&format!("\\{:03o}", byte)),
+            byte => quoted.push(byte as char),
+        }
+    }
+    quoted.push('"');
+    quoted
+}
+
+/// Splits two paths joined by `separator`, like the ones on a `diff --git`
+/// line. Unquoted paths may contain the separator themselves; they are split
+/// where both halves name the same file.
+pub fn split_paths(value: &str, separator: &str) -> Option<(String, String)> {
+    if let Some((first, rest)) = unquote(value) {
+        if let Some(second) = rest.strip_prefix(separator) {
+            return Some((first, unquote_path(second)));
+        }
+        return None;
+    }
+
+    let splits = value
+        .match_indices(separator)
+        .map(|(i, _)| (&value[..i], &value[i + separator.len()..]))
+        .collect::<Vec<(&str, &str)>>();
+
+    if value.ends_with('"') {
+        for (first, second) in splits.iter() {
+            if let Some((second, rest)) = unquote(second) {
+                if rest.is_empty() {
+                    return Some((first.to_string(), second));
+                }
+            }
+        }
+    }
+
+    splits
+        .iter()
+        .find(|(first, second)| {
+            first == second
+                || strip_component(first).is_some_and(|name| Some(name) == strip_component(second))
+        })
+        .or(splits.first())
+        .map(|(first, second)| (first.to_string(), second.to_string()))
+}
+
+// Path without its first component, which is the `a/` or `b/` prefix.
+fn strip_component(path: &str) -> Option<&str> {
+    path.find('/').map(|i| &path[i + 1..])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn decodes_escapes() {
+        assert_eq!(unquote_path(r#""a/h\303\251llo.txt""#), "a/héllo.txt");
+        assert_eq!(unquote_path(r#""a/tab\there""#), "a/tab\there");
+        assert_eq!(unquote_path(r#""a/\"quoted\"""#), "a/\"quoted\"");
+        assert_eq!(unquote_path(r#""a/back\\slash""#), "a/back\\slash");
+        assert_eq!(unquote_path("a/plain.txt"), "a/plain.txt");
+    }
+
+    #[test]
+    fn keeps_the_bytes_of_names_that_are_not_utf8() {
+        assert_eq!(
+            unquote_path_bytes(r#""a/\377.txt""#),
+            b"a/\xff.txt".to_vec()
+        );
+        assert_eq!(unquote_path(r#""a/\377.txt""#), "a/\u{fffd}.txt");
+        assert_eq!(
+            unquote_bytes(r#""a/\377" rest"#),
+            Some((b"a/\xff".to_vec(), " rest"))
+        );
+    }
+
+    #[test]
+    fn needs_a_closing_quote() {
+        assert_eq!(unquote(r#""a/unterminated"#), None);
+        assert_eq!(unquote(r#""a/escaped\""#), None);
+        assert_eq!(unquote_path(r#""a/unterminated"#), r#""a/unterminated"#);
+    }
+
+    #[test]
+    fn quotes_what_it_unquotes() {
+        let path = "dir/tab\there \"quoted\" back\\slash héllo\x01";
+        let quoted = quote_path(path);
+        assert_eq!(
+            quoted,
+            r#""dir/tab\there \"quoted\" back\\slash h\303\251llo\001""#
+        );
+        assert_eq!(unquote(&quoted), Some((path.to_owned(), "")));
+        assert_eq!(quote_path("dir/plain name.txt"), "dir/plain name.txt");
+    }
+
+    fn split(value: &str) -> Option<(String, String)> {
+        split_paths(value, " ")
+    }
+
+    #[test]
+    fn splits_names_with_spaces() {
+        assert_eq!(
+            split("a/my file b/my file"),
+            Some(("a/my file".to_owned(), "b/my file".to_owned()))
+        );
+        assert_eq!(
+            split("a/x b/y b/x b/y"),
+            Some(("a/x b/y".to_owned(), "b/x b/y".to_owned()))
+        );
+    }
+
+    #[test]
+    fn splits_quoted_and_unquoted_names() {
+        assert_eq!(
+            split(r#""a/h\303\251llo.txt" "b/h\303\251llo.txt""#),
+            Some(("a/héllo.txt".to_owned(), "b/héllo.txt".to_owned()))
+        );
+        assert_eq!(
+            split(r#""a/tab\there" b/plain"#),
+            Some(("a/tab\there".to_owned(), "b/plain".to_owned()))
+        );
+        assert_eq!(
+            split(r#"a/plain "b/tab\there""#),
+            Some(("a/plain".to_owned(), "b/tab\there".to_owned()))
+        );
+    }
+}
@@ -0,0 +1,240 @@
+/*
+ * `git range-diff` output: one line per pair of commits of the two ranges,
+ * like `2:  a8042b7 ! 2:  d0480e1 Change five`, followed for modified pairs
+ * by a diff between the two patches, indented by four spaces. Its lines have
+ * two markers, the first for the outer diff between the patches and the
+ * second for the inner diff of the patch itself.
+ */
+
+use regex::Regex;
+
+use super::{LineType, RangeDiffLine, RangeDiffPair, RangeDiffStatus};
+
+lazy_static! {
+    static ref PAIR: Regex =
+        Regex::new(r#"^\s*(-|\d+):\s+(-+|[0-9a-f]+) ([<>=!]) \s*(-|\d+):\s+(-+|[0-9a-f]+) ?(.*)$"#)
+            .unwrap();
+}
+
+static INDENT: &str = "    ";
+
+/// Whether the input looks like `git range-diff` output.
+pub fn is_range_diff(input: &str) -> bool {
+    input
+        .lines()
+        .find(|line| !line.is_empty())
+        .is_some_and(|line| PAIR.is_match(line))
+}
+
+/// Parses the pairs of commits and the diffs of the modified ones.
+pub fn parse_range_diff(input: &str) -> Vec<RangeDiffPair> {
+    let mut pairs: Vec<RangeDiffPair> = Vec::new();
+
+    for line in input.lines() {
+        if let Some(captures) = PAIR.captures(line) {
+            let index = |value: &str| value.parse().ok();
+            let sha = |value: &str| Some(value.to_owned()).filter(|sha| !sha.starts_with('-'));
+            pairs.push(RangeDiffPair {
+                old_index: index(&captures[1]),
+                old_sha: sha(&captures[2]),
+                status: match &captures[3] {
+                    "=" => RangeDiffStatus::Equal,
+                    "<" => RangeDiffStatus::Dropped,
+                    ">" => RangeDiffStatus::Added,
+                    _ => RangeDiffStatus::Modified,
+                },
+                new_index: index(&captures[4]),
+                new_sha: sha(&captures[5]),
+                subject: captures[6].to_owned(),
+                lines: Vec::new(),
+            });
+        } else if let Some(pair) = pairs.last_mut() {
+            if line.starts_with(INDENT) || line.is_empty() {
+                pair.lines
+                    .push(parse_line(line.get(INDENT.len()..).unwrap_or("")));
+            }
+        }
+    }
+
+    // A diff ends with an empty line before the next pair.
+    for pair in pairs.iter_mut() {
+        while pair.lines.last().is_some_and(|line| {
+            line.outer.is_none() && line.inner.is_none() && line.content.is_empty()
+        }) {
+            pair.lines.pop();
+        }
+    }
+
+    pairs
+}
+
+fn parse_line(line: &str) -> RangeDiffLine {
+    // The outer diff has hunk headers of its own, like `@@ Commit message`.
+    if line.starts_with("@@") || line.is_empty() {
+        return RangeDiffLine {
+            outer: None,
+            inner: None,
+            content: line.to_owned(),
+        };
+    }
+
+    let marker = |c: Option<char>| match c {
+        Some('+') => Some(LineType::Inserts),
+        Some('-') => Some(LineType::Deletes),
+        Some(' ') => Some(LineType::Context),
+        _ => None,
+    };
+    let mut chars = line.chars();
+    let outer = marker(chars.next());
+    let rest = chars.as_str();
+    let inner = marker(rest.chars().next());
+
+    RangeDiffLine {
+        outer,
+        content: if inner.is_some() {
+            rest[1..].to_owned()
+        } else {
+            rest.to_owned()
+        },
+        inner,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    static RANGE_DIFF: &str = "1:  a8042b7 = 1:  c3f1d2e Change one
+2:  d0480e1 ! 2:  b1e2f3a Change two
+    @@ Metadata
+    -    Change two
+    +    Change 2
+
+    @@ src/x.rs
+      fn x() {
+    --    a
+    +-    b
+    ++    c
+      }
+
+3:  1234567 < -:  ------- Change three
+-:  ------- > 3:  89abcde Change four
+";
+
+    fn markers(line: &RangeDiffLine) -> (Option<LineType>, Option<LineType>, &str) {
+        (
+            line.outer.clone(),
+            line.inner.clone(),
+            line.content.as_str(),
+        )
+    }
+
+    #[test]
+    fn detects_range_diffs_by_their_first_line() {
+        assert!(is_range_diff(RANGE_DIFF));
+        assert!(is_range_diff("\n-:  ------- > 1:  89abcde Added\n"));
+        assert!(!is_range_diff("--- a/x\n+++ b/x\n"));
+        assert!(!is_range_diff(
+            "Change one\n1:  a8042b7 = 1:  c3f1d2e Change one\n"
+        ));
+    }
+
+    #[test]
+    fn parses_pairs_of_commits() {
+        let pairs = parse_range_diff(RANGE_DIFF);
+        let summary = pairs
+            .iter()
+            .map(|pair| {
+                (
+                    pair.old_index,
+                    pair.old_sha.as_deref(),
+                    pair.status.clone(),
+                    pair.new_index,
+                    pair.new_sha.as_deref(),
+                    pair.subject.as_str(),
+                )
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(
+            summary,
+            vec![
+                (
+                    Some(1),
+                    Some("a8042b7"),
+                    RangeDiffStatus::Equal,
+                    Some(1),
+                    Some("c3f1d2e"),
+                    "Change one"
+                ),
+                (
+                    Some(2),
+                    Some("d0480e1"),
+                    RangeDiffStatus::Modified,
+                    Some(2),
+                    Some("b1e2f3a"),
+                    "Change two"
+                ),
+                (
+                    Some(3),
+                    Some("1234567"),
+                    RangeDiffStatus::Dropped,
+                    None,
+                    None,
+                    "Change three"
+                ),
+                (
+                    None,
+                    None,
+                    RangeDiffStatus::Added,
+                    Some(3),
+                    Some("89abcde"),
+                    "Change four"
+                ),
+            ]
+        );
+        assert!(pairs[0].lines.is_empty());
+        assert!(pairs[2].lines.is_empty());
+    }
+
+    #[test]
+    fn parses_both_markers_of_diff_lines() {
+        let pairs = parse_range_diff(RANGE_DIFF);
+        let lines = pairs[1].lines.iter().map(markers).collect::<Vec<_>>();
+        assert_eq!(
+            lines,
+            vec![
+                (None, None, "@@ Metadata"),
+                (
+                    Some(LineType::Deletes),
+                    Some(LineType::Context),
+                    "   Change two"
+                ),
+                (
+                    Some(LineType::Inserts),
+                    Some(LineType::Context),
+                    "   Change 2"
+                ),
+                (None, None, ""),
+                (None, None, "@@ src/x.rs"),
+                (Some(LineType::Context), Some(LineType::Context), "fn x() {"),
+                (Some(LineType::Deletes), Some(LineType::Deletes), "    a"),
+                (Some(LineType::Inserts), Some(LineType::Deletes), "    b"),
+                (Some(LineType::Inserts), Some(LineType::Inserts), "    c"),
+                (Some(LineType::Context), Some(LineType::Context), "}"),
+            ]
+        );
+    }
+
+    #[test]
+    fn keeps_lines_with_only_an_outer_marker() {
+        assert_eq!(
+            markers(&parse_line("+")),
+            (Some(LineType::Inserts), None, "")
+        );
+        assert_eq!(
+            markers(&parse_line("-x")),
+            (Some(LineType::Deletes), None, "x")
+        );
+        assert_eq!(markers(&parse_line("@@ x")), (None, None, "@@ x"));
+    }
+}
@@ -0,0 +1,205 @@
+/*
+ * Hides changes that only touch whitespace, like `git diff -w`. A deleted
+ * line and an inserted one that only differ in whitespace become a single
+ * context line with the new content. Line counts of the blocks do not change,
+ * so their headers stay valid.
+ */
+
+use super::{Block, File, Line, LineType};
+use crate::difference::myers;
+
+/// Folds whitespace-only changes into context and drops the blocks and
+/// files left without changes. Returns how many files were dropped.
+pub fn hide_whitespace_changes(files: &mut Vec<File>) -> usize {
+    let count = files.len();
+    let mut is_hidden = files
+        .iter_mut()
+        .map(hide_file_whitespace_changes)
+        .collect::<Vec<bool>>()
+        .into_iter();
+    files.retain(|_| !is_hidden.next().unwrap_or(false));
+    count - files.len()
+}
+
+/// Folds the whitespace-only changes of a single file, for files read one at
+/// a time. Returns whether nothing is left to show.
+pub fn hide_file_whitespace_changes(file: &mut File) -> bool {
+    // Combined diffs have a column per parent and binary files no lines.
+    if file.is_combined || file.is_binary || file.blocks.is_empty() {
+        return false;
+    }
+
+    for block in file.blocks.iter_mut() {
+        fold_block(block);
+    }
+    file.blocks.retain(|block| {
+        block
+            .lines
+            .iter()
+            .any(|line| line.line_type != Some(LineType::Context))
+    });
+
+    let count_lines = |line_type: LineType| {
+        file.blocks
+            .iter()
+            .flat_map(|block| block.lines.iter())
+            .filter(|line| line.line_type == Some(line_type.to_owned()))
+            .count()
+    };
+    let added_lines = count_lines(LineType::Inserts);
+    let deleted_lines = count_lines(LineType::Deletes);
+    file.added_lines = added_lines;
+    file.deleted_lines = deleted_lines;
+
+    file.blocks.is_empty() && !has_other_changes(file)
+}
+
+// Changes that are not in the lines, which keep a file worth showing.
+fn has_other_changes(file: &File) -> bool {
+    file.is_new
+        || file.is_deleted
+        || file.is_rename
+        || file.is_copy
+        || file.is_type_change
+        || file.new_mode.is_some()
+}
+
+fn fold_block(block: &mut Block) {
+    let mut lines = Vec::new();
+    let mut deleted: Vec<Line> = Vec::new();
+    let mut inserted: Vec<Line> = Vec::new();
+    for line in block.lines.drain(..) {
+        match line.line_type {
+            Some(LineType::Deletes) if inserted.is_empty() => deleted.push(line),
+            Some(LineType::Inserts) => inserted.push(line),
+            _ => {
+                fold_changes(&mut lines, &mut deleted, &mut inserted);
+                if line.line_type == Some(LineType::Deletes) {
+                    deleted.push(line);
+                } else {
+                    lines.push(line);
+                }
+            }
+        }
+    }
+    fold_changes(&mut lines, &mut deleted, &mut inserted);
+    block.lines = lines;
+}
+
+/*
+ * Pairs the deleted lines of a change with the inserted ones, in order, by
+ * their content without whitespace, and turns every pair into context.
+ */
+fn fold_changes(lines: &mut Vec<Line>, deleted: &mut Vec<Line>, inserted: &mut Vec<Line>) {
+    let strip = |lines: &Vec<Line>| {
+        lines
+            .iter()
+            .map(|line| {
+                let content = line.content.get(1..).unwrap_or("");
+                let content = content
+                    .chars()
+                    .filter(|c| !c.is_whitespace())
+                    .collect::<String>();
+                (content, line.no_newline_at_eof)
+            })
+            .collect::<Vec<(String, bool)>>()
+    };
+    let mut pairs = Vec::new();
+    myers(&strip(deleted), &strip(inserted), 0, 0, &mut pairs);
+
+    let (mut i, mut j) = (0, 0);
+    for (di, ij) in pairs
+        .into_iter()
+        .chain(Some((deleted.len(), inserted.len())))
+    {
+        lines.extend_from_slice(&deleted[i..di]);
+        lines.extend_from_slice(&inserted[j..ij]);
+        if di < deleted.len() {
+            let mut line = inserted[ij].to_owned();
+            line.content = format!(" {}", line.content.get(1..).unwrap_or(""));
+            line.line_type = Some(LineType::Context);
+            line.old_number = deleted[di].old_number;
+            lines.push(line);
+        }
+        i = di + 1;
+        j = ij + 1;
+    }
+
+    deleted.clear();
+    inserted.clear();
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parse::parse_diff;
+
+    fn line_types(file: &File) -> Vec<(Option<LineType>, String)> {
+        file.blocks
+            .iter()
+            .flat_map(|block| block.lines.iter())
+            .map(|line| (line.line_type.clone(), line.content.clone()))
+            .collect()
+    }
+
+    #[test]
+    fn folds_reindented_lines_into_context() {
+        let mut files =
+            parse_diff("--- a/x\n+++ b/x\n@@ -1,3 +1,3 @@\n-if a {\n-b\n+if a  {\n+    c\n }\n");
+        assert_eq!(hide_whitespace_changes(&mut files), 0);
+        assert_eq!(
+            line_types(&files[0]),
+            vec![
+                (Some(LineType::Context), " if a  {".to_owned()),
+                (Some(LineType::Deletes), "-b".to_owned()),
+                (Some(LineType::Inserts), "+    c".to_owned()),
+                (Some(LineType::Context), " }".to_owned()),
+            ]
+        );
+        assert_eq!(files[0].blocks[0].lines[0].old_number, Some(1));
+        assert_eq!(files[0].blocks[0].lines[0].new_number, Some(1));
+        assert_eq!((files[0].added_lines, files[0].deleted_lines), (1, 1));
+    }
+
+    #[test]
+    fn drops_blocks_and_files_left_without_changes() {
+        let mut files = parse_diff(
+            "--- a/x
++++ b/x
+@@ -1 +1 @@
+-a b
++a  b
+@@ -9 +9 @@
+-c
++d
+--- a/y
++++ b/y
+@@ -1 +1 @@
+-\tz
++  z
+",
+        );
+        assert_eq!(hide_whitespace_changes(&mut files), 1);
+        assert_eq!(files.len(), 1);
+        assert_eq!(files[0].blocks.len(), 1);
+        assert_eq!(files[0].blocks[0].old_start, Some(9));
+    }
+
+    #[test]
+    fn keeps_files_with_other_changes() {
+        let mut files = parse_diff(
+            "diff --git a/x b/y
+similarity index 90%
+rename from x
+rename to y
+--- a/x
++++ b/y
+@@ -1 +1 @@
+-a
++a \n",
+        );
+        assert_eq!(hide_whitespace_changes(&mut files), 0);
+        assert!(files[0].is_rename);
+        assert!(files[0].blocks.is_empty());
+    }
+}
@@ -0,0 +1,424 @@
+/*
+ * Applies the blocks of a parsed file to text, like `patch` does: a block
+ * whose lines are not found where its header says is searched for above and
+ * below, and with fuzz, up to that many lines of its leading and trailing
+ * context may be ignored. Lines are compared without a carriage return at
+ * their end, and the lines a block adds to content ending its lines with
+ * one are given one too.
+ */
+
+use std::borrow::Cow;
+
+use crate::parse::{Block, File, LineType};
+
+// Default for how far a block is searched for from where its header puts it.
+const DEFAULT_MAX_OFFSET: usize = 1000;
+
+#[derive(Clone, Debug)]
+pub struct ApplyOptions {
+    // Lines of leading and trailing context that may be ignored.
+    pub fuzz: usize,
+    // How far a block may move from where its header puts it, unlimited
+    // without a value. Every position searched compares the whole block, so
+    // without a limit a block that does not apply costs a scan of the whole
+    // content.
+    pub max_offset: Option<usize>,
+    // Turns the new content back into the old one.
+    pub reverse: bool,
+}
+
+impl Default for ApplyOptions {
+    fn default() -> ApplyOptions {
+        ApplyOptions {
+            fuzz: 0,
+            max_offset: Some(DEFAULT_MAX_OFFSET),
+            reverse: false,
+        }
+    }
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct RejectedHunk {
+    // Index of the block in the file.
+    pub block: usize,
+    // Line the block was expected at.
+    pub line: usize,
+    pub message: String,
+}
+
+struct Hunk<'a> {
+    old_lines: Vec<&'a str>,
+    new_lines: Vec<&'a str>,
+    // Leading and trailing context lines, which fuzz may leave out.
+    leading: usize,
+    trailing: usize,
+    // Start of the old lines, 0-based.
+    start: usize,
+    // Whether the last old or new line has no newline, so the block ends the file.
+    old_no_newline: bool,
+    new_no_newline: bool,
+}
+
+/// Applies `file` to `content`, returning the new content or why each block
+/// that did not apply was rejected.
+pub fn apply(
+    content: &str,
+    file: &File,
+    options: &ApplyOptions,
+) -> Result<String, Vec<RejectedHunk>> {
+    if file.is_combined || file.is_binary {
+        return Err(vec![RejectedHunk {
+            block: 0,
+            line: 0,
+            message: "Only text diffs between two files can be applied.".to_owned(),
+        }]);
+    }
+
+    let mut lines = content.split('\n').collect::<Vec<&str>>();
+    let mut ends_with_newline = content.ends_with('\n') || content.is_empty();
+    if ends_with_newline {
+        lines.pop();
+    }
+    let content_no_newline = !ends_with_newline;
+
+    let mut output: Vec<Cow<str>> = Vec::new();
+    let mut rejected = Vec::new();
+    // Next line of the content to copy, and how far the blocks moved so far.
+    let mut position = 0;
+    let mut offset = 0isize;
+
+    for (index, block) in file.blocks.iter().enumerate() {
+        let hunk = get_hunk(block, options.reverse);
+        let expected = (hunk.start as isize + offset).max(0) as usize;
+
+        let found = (0..=options.fuzz.min(hunk.leading.max(hunk.trailing)))
+            .filter_map(|fuzz| {
+                let skip_start = fuzz.min(hunk.leading);
+                let skip_end = fuzz.min(hunk.trailing);
+                let old_lines = &hunk.old_lines[skip_start..hunk.old_lines.len() - skip_end];
+                // Only a block ending with its last line tells where the text ends.
+                let no_newline = if skip_end == 0 {
+                    Some(hunk.old_no_newline)
+                } else {
+                    None
+                };
+                let ends_right = |start: usize| {
+                    let at_end = content_no_newline && start + old_lines.len() == lines.len();
+                    no_newline.is_none_or(|no_newline| no_newline == at_end)
+                };
+                find_lines(
+                    &lines,
+                    old_lines,
+                    expected + skip_start,
+                    position,
+                    options,
+                    ends_right,
+                )
+                .map(|start| (start, skip_start, skip_end))
+            })
+            .next();
+
+        match found {
+            Some((start, skip_start, skip_end)) => {
+                // Context left out by the fuzz is kept as it is in the content.
+                output.extend(lines[position..start].iter().map(|line| Cow::from(*line)));
+                let crlf = lines
+                    .get(start)
+                    .or_else(|| start.checked_sub(1).and_then(|i| lines.get(i)))
+                    .is_some_and(|line| line.ends_with('\r'));
+                let new_lines = &hunk.new_lines[skip_start..hunk.new_lines.len() - skip_end];
+                for (i, line) in new_lines.iter().enumerate() {
+                    let line = without_cr(line);
+                    let is_last = i + 1 == new_lines.len() && skip_end == 0;
+                    if crlf && !(is_last && hunk.new_no_newline) {
+                        output.push(Cow::from(format!("{}\r", line)));
+                    } else {
+                        output.push(Cow::from(line));
+                    }
+                }
+                position = start + hunk.old_lines.len() - skip_start - skip_end;
+                offset = start as isize - skip_start as isize - hunk.start as isize;
+
+                if hunk.old_no_newline || hunk.new_no_newline {
+                    ends_with_newline = !hunk.new_no_newline;
+                }
+            }
+            None => rejected.push(RejectedHunk {
+                block: index,
+                line: expected + 1,
+                message: format!(
+                    "Could not find the {} lines of block {} near line {}.",
+                    hunk.old_lines.len(),
+                    index + 1,
+                    expected + 1
+                ),
+            }),
+        }
+    }
+
+    if !rejected.is_empty() {
+        return Err(rejected);
+    }
+
+    output.extend(lines[position..].iter().map(|line| Cow::from(*line)));
+    let mut result = output.join("\n");
+    if ends_with_newline && !output.is_empty() {
+        result.push('\n');
+    }
+    Ok(result)
+}
+
+fn get_hunk(block: &Block, reverse: bool) -> Hunk<'_> {
+    let (removed, added) = if reverse {
+        (LineType::Inserts, LineType::Deletes)
+    } else {
+        (LineType::Deletes, LineType::Inserts)
+    };
+
+    let mut hunk = Hunk {
+        old_lines: Vec::new(),
+        new_lines: Vec::new(),
+        leading: 0,
+        trailing: 0,
+        start: 0,
+        old_no_newline: false,
+        new_no_newline: false,
+    };
+
+    let mut changed = false;
+    for line in block.lines.iter() {
+        let content = line.content.get(1..).unwrap_or("");
+        let line_type = line.line_type.as_ref();
+        if line_type != Some(&added) {
+            hunk.old_lines.push(content);
+            hunk.old_no_newline = line.no_newline_at_eof;
+        }
+        if line_type != Some(&removed) {
+            hunk.new_lines.push(content);
+            hunk.new_no_newline = line.no_newline_at_eof;
+        }
+
+        if line_type == Some(&LineType::Context) {
+            if changed {
+                hunk.trailing += 1;
+            } else {
+                hunk.leading += 1;
+            }
+        } else {
+            changed = true;
+            hunk.trailing = 0;
+        }
+    }
+
+    // An empty range starts at the line before it.
+    let (start, count) = if reverse {
+        (block.new_start, block.new_count)
+    } else {
+        (block.old_start, block.old_count)
+    };
+    let start = start.unwrap_or(0);
+    hunk.start = if count == Some(0) {
+        start
+    } else {
+        start.saturating_sub(1)
+    };
+
+    hunk
+}
+
+// Searches for `needle` at `expected`, then alternately below and above it,
+// never before `min_start` where the previous block ended, at starts
+// `ends_right` accepts for where the text ends.
+fn find_lines(
+    lines: &[&str],
+    needle: &[&str],
+    expected: usize,
+    min_start: usize,
+    options: &ApplyOptions,
+    ends_right: impl Fn(usize) -> bool,
+) -> Option<usize> {
+    let matches = |start: usize| {
+        start >= min_start
+            && ends_right(start)
+            && start + needle.len() <= lines.len()
+            && lines[start..start + needle.len()]
+                .iter()
+                .zip(needle.iter())
+                .all(|(line, other)| without_cr(line) == without_cr(other))
+    };
+
+    let max_offset = options.max_offset.unwrap_or(lines.len() + needle.len());
+    (0..=max_offset)
+        .flat_map(|distance| {
+            let below = Some(expected + distance);
+            let above = if distance > 0 {
+                expected.checked_sub(distance)
+            } else {
+                None
+            };
+            below.into_iter().chain(above)
+        })
+        .find(|start| matches(*start))
+}
+
+fn without_cr(line: &str) -> &str {
+    line.strip_suffix('\r').unwrap_or(line)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parse::parse_diff;
+
+    static CONTENT: &str = "one\ntwo\nthree\nfour\nfive\nsix\nseven\n";
+    static PATCH: &str = "--- a/x
++++ b/x
+@@ -2,3 +2,3 @@
+ two
+-three
++THREE
+ four
+";
+
+    static PATCH_ONE: &str = "--- a/x
++++ b/x
+@@ -1 +1 @@
+-one
++ONE
+";
+
+    fn patch(diff: &str) -> File {
+        parse_diff(diff).remove(0)
+    }
+
+    #[test]
+    fn applies_blocks_where_their_header_says() {
+        let result = apply(CONTENT, &patch(PATCH), &ApplyOptions::default());
+        assert_eq!(result.unwrap(), "one\ntwo\nTHREE\nfour\nfive\nsix\nseven\n");
+    }
+
+    #[test]
+    fn applies_moved_blocks_at_an_offset() {
+        let content = format!("zero\nzero\n{}", CONTENT);
+        let result = apply(&content, &patch(PATCH), &ApplyOptions::default());
+        assert_eq!(
+            result.unwrap(),
+            "zero\nzero\none\ntwo\nTHREE\nfour\nfive\nsix\nseven\n"
+        );
+
+        let options = ApplyOptions {
+            max_offset: Some(1),
+            ..ApplyOptions::default()
+        };
+        let rejected = apply(&content, &patch(PATCH), &options).unwrap_err();
+        assert_eq!(rejected.len(), 1);
+        assert_eq!(rejected[0].block, 0);
+        assert_eq!(rejected[0].line, 2);
+
+        // Without a limit of its own a block is only searched for so far.
+        let content = format!("{}{}", "zero\n".repeat(DEFAULT_MAX_OFFSET + 1), CONTENT);
+        assert!(apply(&content, &patch(PATCH), &ApplyOptions::default()).is_err());
+    }
+
+    #[test]
+    fn ignores_context_with_fuzz() {
+        let content = CONTENT.replace("four", "FOUR");
+        let rejected = apply(&content, &patch(PATCH), &ApplyOptions::default());
+        assert!(rejected.is_err());
+
+        let options = ApplyOptions {
+            fuzz: 1,
+            ..ApplyOptions::default()
+        };
+        let result = apply(&content, &patch(PATCH), &options);
+        assert_eq!(result.unwrap(), "one\ntwo\nTHREE\nFOUR\nfive\nsix\nseven\n");
+    }
+
+    #[test]
+    fn reverts_blocks_in_reverse() {
+        let content = "one\ntwo\nTHREE\nfour\nfive\nsix\nseven\n";
+        let options = ApplyOptions {
+            reverse: true,
+            ..ApplyOptions::default()
+        };
+        let result = apply(content, &patch(PATCH), &options);
+        assert_eq!(result.unwrap(), CONTENT);
+    }
+
+    #[test]
+    fn rejects_blocks_that_are_not_found() {
+        let file = patch(
+            "--- a/x
++++ b/x
+@@ -1,2 +1,2 @@
+-one
++ONE
+ two
+@@ -5,2 +5,2 @@
+ five
+-missing
++six
+",
+        );
+        let rejected = apply(CONTENT, &file, &ApplyOptions::default()).unwrap_err();
+        assert_eq!(rejected.len(), 1);
+        assert_eq!(rejected[0].block, 1);
+        assert_eq!(rejected[0].line, 5);
+    }
+
+    #[test]
+    fn keeps_missing_newlines_at_the_end() {
+        let file = patch(
+            "--- a/x
++++ b/x
+@@ -1 +1 @@
+-one
++ONE
+\\ No newline at end of file
+",
+        );
+        let result = apply("one\n", &file, &ApplyOptions::default());
+        assert_eq!(result.unwrap(), "ONE");
+    }
+
+    #[test]
+    fn compares_lines_without_carriage_returns() {
+        let content = CONTENT.replace('\n', "\r\n");
+        let result = apply(&content, &patch(PATCH), &ApplyOptions::default());
+        assert_eq!(
+            result.unwrap(),
+            "one\r\ntwo\r\nTHREE\r\nfour\r\nfive\r\nsix\r\nseven\r\n"
+        );
+
+        let file = patch(&PATCH.replace('\n', "\r\n"));
+        let result = apply(CONTENT, &file, &ApplyOptions::default());
+        assert_eq!(result.unwrap(), "one\ntwo\nTHREE\nfour\nfive\nsix\nseven\n");
+    }
+
+    #[test]
+    fn rejects_blocks_that_do_not_end_the_text_as_it_does() {
+        let file = patch(
+            "--- a/x
++++ b/x
+@@ -1 +1 @@
+-one
+\\ No newline at end of file
++ONE
+",
+        );
+        assert_eq!(
+            apply("one", &file, &ApplyOptions::default()).unwrap(),
+            "ONE\n"
+        );
+        assert!(apply("one\n", &file, &ApplyOptions::default()).is_err());
+        assert!(apply("one\ntwo", &file, &ApplyOptions::default()).is_err());
+
+        // A block with a newline after its last line does not end a text
+        // without one.
+        assert!(apply("one", &patch(PATCH_ONE), &ApplyOptions::default()).is_err());
+        assert_eq!(
+            apply("one\n", &patch(PATCH_ONE), &ApplyOptions::default()).unwrap(),
+            "ONE\n"
+        );
+    }
+}
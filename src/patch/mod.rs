@@ -0,0 +1,80 @@
+/*
+ * Turns parsed files back into patches.
+ */
+
+mod apply;
+mod select;
+mod write;
+
+use crate::parse::{Block, LineType};
+
+pub use self::apply::{apply, ApplyOptions, RejectedHunk};
+pub use self::select::{select, split_block, Selection};
+pub use self::write::write_unified;
+
+/*
+ * Ranges of a block as (old start, old count, new start, new count), counted
+ * from its lines. The new range starts where the old one does, moved by
+ * `offset`, the lines added minus the lines removed by the blocks before it.
+ */
+pub(crate) fn get_block_ranges(block: &Block, offset: &mut isize) -> (usize, usize, usize, usize) {
+    let old_count = block
+        .lines
+        .iter()
+        .filter(|line| line.line_type != Some(LineType::Inserts))
+        .count();
+    let new_count = block
+        .lines
+        .iter()
+        .filter(|line| line.line_type != Some(LineType::Deletes))
+        .count();
+
+    // An empty range starts at the line before it.
+    let old_start = block.old_start.unwrap_or(0);
+    let old_first = if old_count == 0 {
+        old_start + 1
+    } else {
+        old_start
+    };
+    let new_first = (old_first as isize + *offset) as usize;
+    let new_start = if new_count == 0 {
+        new_first - 1
+    } else {
+        new_first
+    };
+    *offset += new_count as isize - old_count as isize;
+
+    (old_start, old_count, new_start, new_count)
+}
+
+// Sets the header and ranges of a block to the ones counted from its lines.
+pub(crate) fn set_ranges(block: &mut Block, ranges: (usize, usize, usize, usize)) {
+    let (old_start, old_count, new_start, new_count) = ranges;
+    block.header = Some(get_block_header(block, ranges));
+    block.old_start = Some(old_start);
+    block.old_count = Some(old_count);
+    block.new_start = Some(new_start);
+    block.new_count = Some(new_count);
+}
+
+fn get_block_header(block: &Block, ranges: (usize, usize, usize, usize)) -> String {
+    let (old_start, old_count, new_start, new_count) = ranges;
+    let mut header = format!(
+        "@@ -{} +{} @@",
+        get_range(old_start, old_count),
+        get_range(new_start, new_count)
+    );
+    if let Some(heading) = &block.section_heading {
+        header.push(' ');
+        header.push_str(heading);
+    }
+    header
+}
+
+fn get_range(start: usize, count: usize) -> String {
+    if count == 1 {
+        start.to_string()
+    } else {
+        format!("{},{}", start, count)
+    }
+}
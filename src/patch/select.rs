@@ -0,0 +1,412 @@
+/*
+ * Partial patches, as `git add -p` builds them. Deletions that are not
+ * selected stay in the file and become context, insertions that are not
+ * selected are dropped. The old side of every block stays where it was, so
+ * only the new ranges and line numbers change.
+ */
+
+use std::collections::HashSet;
+
+use super::{get_block_ranges, set_ranges};
+use crate::parse::{Block, File, Line, LineType};
+
+static DEV_NULL: &str = "/dev/null";
+
+#[derive(Clone, Debug, Default)]
+pub struct Selection {
+    blocks: HashSet<usize>,
+    lines: HashSet<(usize, usize)>,
+}
+
+impl Selection {
+    pub fn new() -> Selection {
+        Selection::default()
+    }
+
+    /// Selects every line of the block at `block`.
+    pub fn select_block(&mut self, block: usize) {
+        self.blocks.insert(block);
+    }
+
+    /// Selects the line at `line` of the block at `block`.
+    pub fn select_line(&mut self, block: usize, line: usize) {
+        self.lines.insert((block, line));
+    }
+
+    pub fn is_selected(&self, block: usize, line: usize) -> bool {
+        self.blocks.contains(&block) || self.lines.contains(&(block, line))
+    }
+}
+
+/// Builds a copy of `file` with only the selected changes. Blocks left
+/// without changes are dropped. Combined and binary diffs cannot be split
+/// and are copied as they are.
+pub fn select(file: &File, selection: &Selection) -> File {
+    let mut selected = file.clone();
+    if file.is_combined || file.is_binary {
+        return selected;
+    }
+
+    selected.blocks = Vec::new();
+    let mut keeps_deletions = false;
+    for (i, block) in file.blocks.iter().enumerate() {
+        let mut lines = Vec::new();
+        for (j, line) in block.lines.iter().enumerate() {
+            let is_selected = selection.is_selected(i, j);
+            match line.line_type {
+                Some(LineType::Deletes) if !is_selected => {
+                    keeps_deletions = true;
+                    lines.push(to_context(line));
+                }
+                Some(LineType::Inserts) if !is_selected => {}
+                _ => lines.push(line.clone()),
+            }
+        }
+
+        if lines
+            .iter()
+            .all(|line| line.line_type == Some(LineType::Context))
+        {
+            continue;
+        }
+
+        let mut block = block.clone();
+        block.lines = lines;
+        match selected.blocks.last_mut() {
+            Some(previous) if overlaps(previous, &block) => merge_blocks(previous, block),
+            _ => selected.blocks.push(block),
+        }
+    }
+
+    // Lines left in the file mean it is neither deleted nor created anymore.
+    if keeps_deletions {
+        keep_file(&mut selected);
+    }
+
+    renumber(&mut selected);
+    selected
+}
+
+fn keep_file(file: &mut File) {
+    if file.is_deleted || file.new_name.as_deref() == Some(DEV_NULL) {
+        file.new_name = file.old_name.clone();
+        file.new_kind = file.old_kind;
+        file.checksum_after = None;
+    }
+    file.is_deleted = false;
+    file.deleted_file_mode = None;
+    file.is_new = false;
+    file.new_file_mode = None;
+}
+
+/// Splits a block into one block per run of changes, like the `s` command of
+/// `git add -p`. The context between two runs is kept by both blocks.
+pub fn split_block(block: &Block) -> Vec<Block> {
+    let is_change = |line: &Line| line.line_type != Some(LineType::Context);
+
+    // Start and end of every run of changes.
+    let mut runs: Vec<(usize, usize)> = Vec::new();
+    for (i, line) in block.lines.iter().enumerate() {
+        if !is_change(line) {
+            continue;
+        }
+        match runs.last_mut() {
+            Some(run) if run.1 == i => run.1 = i + 1,
+            _ => runs.push((i, i + 1)),
+        }
+    }
+    if runs.len() < 2 {
+        return vec![block.clone()];
+    }
+
+    let count_old = |lines: &[Line]| {
+        lines
+            .iter()
+            .filter(|line| line.line_type != Some(LineType::Inserts))
+            .count()
+    };
+    let count_new = |lines: &[Line]| {
+        lines
+            .iter()
+            .filter(|line| line.line_type != Some(LineType::Deletes))
+            .count()
+    };
+    // First line of each side, as an empty range starts at the line before it.
+    let old_first = block.old_start.unwrap_or(0) + if count_old(&block.lines) == 0 { 1 } else { 0 };
+    let new_first = block.new_start.unwrap_or(0) + if count_new(&block.lines) == 0 { 1 } else { 0 };
+
+    (0..runs.len())
+        .map(|i| {
+            let start = if i == 0 { 0 } else { runs[i - 1].1 };
+            let end = runs.get(i + 1).map_or(block.lines.len(), |next| next.0);
+
+            let mut piece = block.clone();
+            piece.lines = block.lines[start..end].to_vec();
+            if i > 0 {
+                piece.section_heading = None;
+            }
+
+            let first = old_first + count_old(&block.lines[..start]);
+            piece.old_start = Some(if count_old(&piece.lines) == 0 {
+                first - 1
+            } else {
+                first
+            });
+
+            // Where the new side of the piece starts relative to the old side.
+            let mut offset = (new_first + count_new(&block.lines[..start])) as isize
+                - (old_first + count_old(&block.lines[..start])) as isize;
+            let ranges = get_block_ranges(&piece, &mut offset);
+            set_ranges(&mut piece, ranges);
+            piece
+        })
+        .collect()
+}
+
+fn to_context(line: &Line) -> Line {
+    let mut line = line.clone();
+    line.content = format!(" {}", line.content.get(1..).unwrap_or(""));
+    line.line_type = Some(LineType::Context);
+    line
+}
+
+// Blocks split apart share their context, so they overlap on the old side.
+fn overlaps(previous: &Block, block: &Block) -> bool {
+    let previous_end = previous
+        .lines
+        .iter()
+        .filter_map(|line| line.old_number)
+        .max();
+    let start = block.lines.iter().filter_map(|line| line.old_number).min();
+    match (previous_end, start) {
+        (Some(previous_end), Some(start)) => start <= previous_end,
+        _ => false,
+    }
+}
+
+// Appends a block to the one it overlaps, without the shared context.
+fn merge_blocks(previous: &mut Block, block: Block) {
+    let previous_end = previous
+        .lines
+        .iter()
+        .filter_map(|line| line.old_number)
+        .max()
+        .unwrap_or(0);
+    previous
+        .lines
+        .extend(block.lines.into_iter().skip_while(|line| {
+            line.line_type == Some(LineType::Context)
+                && line.old_number.is_some_and(|number| number <= previous_end)
+        }));
+}
+
+// Recomputes the new ranges, line numbers and line counts of the file.
+fn renumber(file: &mut File) {
+    let mut offset = 0;
+    file.added_lines = 0;
+    file.deleted_lines = 0;
+
+    for block in file.blocks.iter_mut() {
+        let ranges = get_block_ranges(block, &mut offset);
+        set_ranges(block, ranges);
+
+        let (old_start, old_count, new_start, new_count) = ranges;
+        let mut old_number = if old_count == 0 {
+            old_start + 1
+        } else {
+            old_start
+        };
+        let mut new_number = if new_count == 0 {
+            new_start + 1
+        } else {
+            new_start
+        };
+        for line in block.lines.iter_mut() {
+            match line.line_type {
+                Some(LineType::Inserts) => {
+                    line.old_number = None;
+                    line.new_number = Some(new_number);
+                    new_number += 1;
+                    file.added_lines += 1;
+                }
+                Some(LineType::Deletes) => {
+                    line.old_number = Some(old_number);
+                    line.new_number = None;
+                    old_number += 1;
+                    file.deleted_lines += 1;
+                }
+                _ => {
+                    line.old_number = Some(old_number);
+                    line.new_number = Some(new_number);
+                    old_number += 1;
+                    new_number += 1;
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parse::parse_diff;
+    use crate::patch::write_unified;
+
+    fn lines(block: &Block) -> Vec<&str> {
+        block
+            .lines
+            .iter()
+            .map(|line| line.content.as_str())
+            .collect()
+    }
+
+    fn headers(blocks: &[Block]) -> Vec<&str> {
+        blocks
+            .iter()
+            .filter_map(|block| block.header.as_deref())
+            .collect()
+    }
+
+    static PATCH: &str = "--- a/x
++++ b/x
+@@ -1,4 +1,4 @@
+ one
+-two
++TWO
+-three
++THREE
+ four
+@@ -10,2 +10,3 @@
+ ten
++eleven
+ twelve
+";
+
+    #[test]
+    fn keeps_unselected_deletions_as_context() {
+        let file = parse_diff(PATCH).remove(0);
+        let mut selection = Selection::new();
+        selection.select_line(0, 2);
+        selection.select_block(1);
+
+        let selected = select(&file, &selection);
+        assert_eq!(
+            lines(&selected.blocks[0]),
+            vec![" one", " two", "+TWO", " three", " four"]
+        );
+        // The line added by the first block moves the second one down.
+        assert_eq!(
+            headers(&selected.blocks),
+            vec!["@@ -1,4 +1,5 @@", "@@ -10,2 +11,3 @@"]
+        );
+        assert_eq!(selected.blocks[0].lines[2].new_number, Some(3));
+        assert_eq!(selected.blocks[1].lines[1].new_number, Some(12));
+        assert_eq!(selected.added_lines, 2);
+        assert_eq!(selected.deleted_lines, 0);
+    }
+
+    #[test]
+    fn keeps_a_deleted_file_with_unselected_lines() {
+        let file = parse_diff(
+            "diff --git a/x b/x
+deleted file mode 100644
+index 1234567..0000000
+--- a/x
++++ /dev/null
+@@ -1,3 +0,0 @@
+-one
+-two
+-three
+",
+        )
+        .remove(0);
+        let mut selection = Selection::new();
+        selection.select_line(0, 1);
+
+        let selected = select(&file, &selection);
+        assert!(!selected.is_deleted);
+        assert_eq!(selected.deleted_file_mode, None);
+        assert_eq!(selected.new_name.as_deref(), Some("x"));
+        assert_eq!(headers(&selected.blocks), vec!["@@ -1,3 +1,2 @@"]);
+        assert_eq!(
+            write_unified(&[selected]),
+            "diff --git a/x b/x
+--- a/x
++++ b/x
+@@ -1,3 +1,2 @@
+ one
+-two
+ three
+"
+        );
+
+        // Deleting every line still deletes the file.
+        selection.select_block(0);
+        let selected = select(&file, &selection);
+        assert!(selected.is_deleted);
+        assert_eq!(selected.new_name.as_deref(), Some("/dev/null"));
+    }
+
+    #[test]
+    fn drops_blocks_without_selected_changes() {
+        let file = parse_diff(PATCH).remove(0);
+        let mut selection = Selection::new();
+        selection.select_block(1);
+
+        let selected = select(&file, &selection);
+        assert_eq!(selected.blocks.len(), 1);
+        assert_eq!(headers(&selected.blocks), vec!["@@ -10,2 +10,3 @@"]);
+    }
+
+    #[test]
+    fn splits_blocks_between_runs_of_changes() {
+        let file = parse_diff(
+            "--- a/x
++++ b/x
+@@ -10,6 +10,7 @@ fn main
+ a
+-b
++B
++B2
+ c
+ d
+-e
++E
+ f
+",
+        )
+        .remove(0);
+
+        let blocks = split_block(&file.blocks[0]);
+        assert_eq!(
+            headers(&blocks),
+            vec!["@@ -10,4 +10,5 @@ fn main", "@@ -12,4 +13,4 @@"]
+        );
+        assert_eq!(lines(&blocks[0]), vec![" a", "-b", "+B", "+B2", " c", " d"]);
+        assert_eq!(lines(&blocks[1]), vec![" c", " d", "-e", "+E", " f"]);
+    }
+
+    #[test]
+    fn splits_blocks_at_the_start_of_the_file() {
+        let file = parse_diff("--- a/x\n+++ b/x\n@@ -1,2 +1,2 @@\n-a\n b\n+X\n").remove(0);
+
+        let blocks = split_block(&file.blocks[0]);
+        assert_eq!(headers(&blocks), vec!["@@ -1,2 +1 @@", "@@ -2 +1,2 @@"]);
+    }
+
+    #[test]
+    fn merges_split_blocks_back_when_selected() {
+        let file = parse_diff(PATCH).remove(0);
+        let mut split = file.clone();
+        split.blocks = split_block(&file.blocks[0]);
+        split.blocks.push(file.blocks[1].clone());
+
+        let mut selection = Selection::new();
+        for i in 0..split.blocks.len() {
+            selection.select_block(i);
+        }
+        let selected = select(&split, &selection);
+        assert_eq!(headers(&selected.blocks), headers(&file.blocks));
+        assert_eq!(lines(&selected.blocks[0]), lines(&file.blocks[0]));
+    }
+}
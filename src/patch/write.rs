@@ -0,0 +1,359 @@
+/*
+ * Writes files in git's diff format, so the output can be fed to `git apply`
+ * no matter which format the files were parsed from. Hunk ranges are
+ * recomputed from the lines, since callers may have edited them.
+ */
+
+use super::{get_block_header, get_block_ranges};
+use crate::parse::{binary, quote, BinaryHunk, BinaryPatchKind, Block, File};
+
+static DEV_NULL: &str = "/dev/null";
+static DEFAULT_MODE: &str = "100644";
+static NO_NEWLINE: &str = "\\ No newline at end of file";
+
+/// Writes the files as a single patch.
+pub fn write_unified(files: &[File]) -> String {
+    let mut lines = Vec::new();
+    for file in files {
+        write_file(&mut lines, file);
+    }
+
+    let mut patch = lines.join("\n");
+    if !patch.is_empty() {
+        patch.push('\n');
+    }
+    patch
+}
+
+fn write_file(lines: &mut Vec<String>, file: &File) {
+    let old_name = real_name(&file.old_name)
+        .or_else(|| real_name(&file.new_name))
+        .unwrap_or("");
+    let new_name = real_name(&file.new_name)
+        .or_else(|| real_name(&file.old_name))
+        .unwrap_or("");
+    // Formats without `/dev/null` names only tell by an empty range.
+    let is_new = file.is_new
+        || file.old_name.as_deref() == Some(DEV_NULL)
+        || has_only_block(file, |block| {
+            block.old_start == Some(0) && block.old_count == Some(0)
+        });
+    let is_deleted = file.is_deleted
+        || file.new_name.as_deref() == Some(DEV_NULL)
+        || has_only_block(file, |block| {
+            block.new_start == Some(0) && block.new_count == Some(0)
+        });
+
+    // Nothing git could apply, like a change of Subversion properties.
+    if file.blocks.is_empty()
+        && !file.is_binary
+        && !file.is_rename
+        && !file.is_copy
+        && !is_new
+        && !is_deleted
+        && file.new_mode.is_none()
+    {
+        return;
+    }
+
+    if file.is_combined {
+        lines.push(format!("diff --cc {}", quote::quote_path(new_name)));
+    } else {
+        lines.push(format!(
+            "diff --git {} {}",
+            prefixed_path("a/", old_name),
+            prefixed_path("b/", new_name)
+        ));
+    }
+    write_extended_headers(lines, file, old_name, new_name, is_new, is_deleted);
+
+    let old_path = if is_new {
+        DEV_NULL.to_owned()
+    } else {
+        prefixed_path("a/", old_name)
+    };
+    let new_path = if is_deleted {
+        DEV_NULL.to_owned()
+    } else {
+        prefixed_path("b/", new_name)
+    };
+
+    if let Some(patch) = &file.binary_patch {
+        lines.push("GIT binary patch".to_owned());
+        write_binary_hunk(lines, &patch.forward);
+        if let Some(reverse) = &patch.reverse {
+            write_binary_hunk(lines, reverse);
+        }
+        return;
+    }
+
+    // The block of a binary file only holds the `Binary files` line.
+    if file.is_binary {
+        lines.push(format!("Binary files {} and {} differ", old_path, new_path));
+        return;
+    }
+
+    if file.blocks.is_empty() {
+        return;
+    }
+
+    // Like git, end names containing spaces with a tab so they are not taken
+    // for a name followed by a timestamp.
+    let end_path = |path: String| {
+        if path.contains(' ') {
+            path + "\t"
+        } else {
+            path
+        }
+    };
+    lines.push(format!("--- {}", end_path(old_path)));
+    lines.push(format!("+++ {}", end_path(new_path)));
+
+    // Lines added minus lines removed by the blocks before the current one.
+    let mut offset = 0isize;
+    for block in file.blocks.iter() {
+        if file.is_combined {
+            lines.push(block.header.to_owned().unwrap_or_default());
+        } else {
+            lines.push(get_block_header(
+                block,
+                get_block_ranges(block, &mut offset),
+            ));
+        }
+        for line in block.lines.iter() {
+            lines.push(line.content.to_owned());
+            if line.no_newline_at_eof {
+                lines.push(NO_NEWLINE.to_owned());
+            }
+        }
+    }
+}
+
+fn write_extended_headers(
+    lines: &mut Vec<String>,
+    file: &File,
+    old_name: &str,
+    new_name: &str,
+    is_new: bool,
+    is_deleted: bool,
+) {
+    if file.is_combined {
+        if let (Some(old_modes), Some(new_mode)) = (&file.old_mode, &file.new_mode) {
+            lines.push(format!("mode {}..{}", old_modes.join(","), new_mode));
+        }
+    } else {
+        if let (Some(old_mode), Some(new_mode)) = (
+            file.old_mode.as_ref().and_then(|modes| modes.first()),
+            &file.new_mode,
+        ) {
+            lines.push(format!("old mode {}", old_mode));
+            lines.push(format!("new mode {}", new_mode));
+        }
+    }
+    // `git apply` only creates and deletes files with these headers.
+    if is_deleted {
+        let mode = file
+            .deleted_file_mode
+            .as_ref()
+            .map_or(DEFAULT_MODE, |v| v.as_str());
+        lines.push(format!("deleted file mode {}", mode));
+    }
+    if is_new {
+        let mode = file
+            .new_file_mode
+            .as_ref()
+            .map_or(DEFAULT_MODE, |v| v.as_str());
+        lines.push(format!("new file mode {}", mode));
+    }
+
+    if file.is_copy || file.is_rename {
+        if let Some(percentage) = file.unchanged_percentage {
+            lines.push(format!("similarity index {}%", percentage));
+        }
+        let action = if file.is_copy { "copy" } else { "rename" };
+        lines.push(format!("{} from {}", action, quote::quote_path(old_name)));
+        lines.push(format!("{} to {}", action, quote::quote_path(new_name)));
+    }
+    if let Some(percentage) = file.changed_percentage {
+        lines.push(format!("dissimilarity index {}%", percentage));
+    }
+
+    if let (Some(before), Some(after)) = (&file.checksum_before, &file.checksum_after) {
+        match &file.mode {
+            Some(mode) if !file.is_combined => {
+                lines.push(format!("index {}..{} {}", before.join(","), after, mode))
+            }
+            _ => lines.push(format!("index {}..{}", before.join(","), after)),
+        }
+    }
+}
+
+fn write_binary_hunk(lines: &mut Vec<String>, hunk: &BinaryHunk) {
+    let kind = match hunk.kind {
+        BinaryPatchKind::Literal => "literal",
+        BinaryPatchKind::Delta => "delta",
+    };
+    lines.push(format!("{} {}", kind, hunk.data.len()));
+    for chunk in binary::deflate(&hunk.data).chunks(52) {
+        lines.push(binary::encode_base85_line(chunk));
+    }
+    lines.push(String::new());
+}
+
+fn has_only_block<F: Fn(&Block) -> bool>(file: &File, predicate: F) -> bool {
+    file.blocks.len() == 1 && predicate(&file.blocks[0])
+}
+
+fn real_name(name: &Option<String>) -> Option<&str> {
+    name.as_ref()
+        .map(|name| name.as_str())
+        .filter(|name| *name != DEV_NULL)
+}
+
+fn prefixed_path(prefix: &str, path: &str) -> String {
+    quote::quote_path(&format!("{}{}", prefix, path))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parse::parse_diff;
+
+    static GIT_PATCH: &str = "diff --git a/a.txt b/a.txt
+index c9e9e05..061a3ba 100644
+--- a/a.txt
++++ b/a.txt
+@@ -1,3 +1,3 @@
+ one
+-two
++TWO
+ three
+@@ -8,3 +8,3 @@ seven
+ eight
+-nine
++NINE
+ ten
+diff --git a/moved.txt b/dir-moved.txt
+similarity index 75%
+rename from moved.txt
+rename to dir-moved.txt
+index a470291..eba2367 100644
+--- a/moved.txt
++++ b/dir-moved.txt
+@@ -3,2 +3,2 @@ y
+ z
+-w
++W
+diff --git a/new.txt b/new.txt
+new file mode 100644
+index 0000000..3e75765
+--- /dev/null
++++ b/new.txt
+@@ -0,0 +1 @@
++new
+diff --git a/old.txt b/old.txt
+deleted file mode 100644
+index 286c5f5..0000000
+--- a/old.txt
++++ /dev/null
+@@ -1 +0,0 @@
+-gone
+diff --git a/run.sh b/run.sh
+old mode 100644
+new mode 100755
+diff --git a/sp ace.txt b/sp ace.txt
+index 1c1206e..a9ebee0 100644
+--- a/sp ace.txt\t
++++ b/sp ace.txt\t
+@@ -1 +1,2 @@
+-last
+\\ No newline at end of file
++last
++more
+\\ No newline at end of file
+";
+
+    #[test]
+    fn writes_git_patches_back_unchanged() {
+        let files = parse_diff(GIT_PATCH);
+        assert_eq!(files.len(), 6);
+        assert_eq!(write_unified(&files), GIT_PATCH);
+    }
+
+    #[test]
+    fn round_trips_through_the_model() {
+        let files = parse_diff(GIT_PATCH);
+        let json = serde_json::to_string(&files).unwrap();
+        let files: Vec<File> = serde_json::from_str(&json).unwrap();
+        let again = parse_diff(&write_unified(&files));
+        assert_eq!(serde_json::to_string(&again).unwrap(), json);
+    }
+
+    #[test]
+    fn recomputes_hunk_ranges_from_the_lines() {
+        let mut files = parse_diff(GIT_PATCH);
+        // Without its deletion the first block only adds a line, which moves
+        // the new range of the second one.
+        files[0].blocks[0].lines.remove(1);
+
+        let patch = write_unified(&files[..1]);
+        let headers = patch
+            .lines()
+            .filter(|line| line.starts_with("@@"))
+            .collect::<Vec<_>>();
+        assert_eq!(headers, vec!["@@ -1,2 +1,3 @@", "@@ -8,3 +9,3 @@ seven"]);
+
+        let again = parse_diff(&patch);
+        assert_eq!(again[0].added_lines, 2);
+        assert_eq!(again[0].deleted_lines, 1);
+    }
+
+    #[test]
+    fn writes_other_formats_as_git_patches() {
+        let context = "*** a.txt\t2026-10-17 04:07:25.000000000 +0000
+--- a.txt\t2026-10-17 04:07:26.000000000 +0000
+***************
+*** 1,3 ****
+  one
+! two
+  three
+--- 1,3 ----
+  one
+! TWO
+  three
+";
+        let patch = write_unified(&parse_diff(context));
+        assert_eq!(
+            patch,
+            "diff --git a/a.txt b/a.txt
+--- a/a.txt
++++ b/a.txt
+@@ -1,3 +1,3 @@
+ one
+-two
++TWO
+ three
+"
+        );
+    }
+
+    #[test]
+    fn round_trips_binary_patches() {
+        // The mode change of `run.sh`, made a binary change.
+        let mut file = parse_diff(GIT_PATCH).remove(4);
+        file.is_binary = true;
+        file.binary_patch = Some(crate::parse::BinaryPatch {
+            forward: BinaryHunk {
+                kind: BinaryPatchKind::Literal,
+                size: 200,
+                data: (0..200u8).collect(),
+            },
+            reverse: None,
+        });
+
+        let again = parse_diff(&write_unified(&[file]));
+        let patch = again[0].binary_patch.as_ref().unwrap();
+        assert_eq!(patch.forward.kind, BinaryPatchKind::Literal);
+        assert_eq!(patch.forward.data, (0..200u8).collect::<Vec<u8>>());
+    }
+}
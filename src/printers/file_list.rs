@@ -9,6 +9,8 @@ static FILE_SUMMARY_LINE: &'static str = include_str!("../templates/file-summary
 
 pub struct FileListPrinter {
     handlebars: Handlebars,
+    // Files left out because they only changed whitespace.
+    hidden_files: usize,
 }
 
 impl FileListPrinter {
@@ -22,36 +24,75 @@ impl FileListPrinter {
             .unwrap();
         FileListPrinter {
             handlebars: handlebars,
+            hidden_files: 0,
         }
     }
 
+    pub fn with_hidden_files(mut self, hidden_files: usize) -> FileListPrinter {
+        self.hidden_files = hidden_files;
+        self
+    }
+
     pub fn render(&mut self, files: &Vec<parse::File>) -> String {
         let file_list = files
             .iter()
-            .map(|file| {
-                self.handlebars.render(
-                    "line",
-                    &json!({
-                        "fileHtmlId": utils::get_html_id(file),
-                        "fileName": utils::get_diff_name(file),
-                        "deletedLines": format!("-{}", file.deleted_lines),
-                        "addedLines": format!("+{}", file.added_lines),
-                        "fileIcon": utils::get_file_type_icon(file).to_owned(),
-                    }),
-                )
-            })
-            .map(|v| v.unwrap())
-            .collect::<Vec<String>>()
-            .join("\n");
+            .map(|file| self.render_line(file))
+            .collect::<Vec<String>>();
+
+        self.render_lines(&file_list)
+    }
+
+    /// Renders the entry of a single file, for files read one at a time.
+    pub fn render_line(&self, file: &parse::File) -> String {
+        self.handlebars
+            .render(
+                "line",
+                &json!({
+                    "fileHtmlId": utils::get_html_id(file),
+                    "fileName": utils::get_diff_name(file),
+                    "deletedLines": format!("-{}", file.deleted_lines),
+                    "addedLines": format!("+{}", file.added_lines),
+                    "fileIcon": utils::get_file_type_icon(file).to_owned(),
+                    "fileTag": utils::get_resolution_tag(file).to_owned(),
+                    "sizeDelta": utils::get_size_delta(file),
+                    "sizeChange": utils::get_size_change(file),
+                }),
+            )
+            .unwrap()
+    }
 
+    /// Renders the list around entries from `render_line`.
+    pub fn render_lines(&self, lines: &[String]) -> String {
         self.handlebars
             .render(
                 "wrapper",
                 &json!({
-                    "filesNumber": files.len(),
-                    "files": file_list,
+                    "filesNumber": lines.len(),
+                    "files": lines.join("\n"),
+                    "hiddenFiles": match self.hidden_files {
+                        0 => None,
+                        1 => Some("1 file with only whitespace changes hidden".to_owned()),
+                        n => Some(format!("{} files with only whitespace changes hidden", n)),
+                    },
                 }),
             )
             .unwrap()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn tells_how_many_files_are_hidden() {
+        let hidden = |count| {
+            FileListPrinter::new()
+                .with_hidden_files(count)
+                .render(&Vec::new())
+        };
+        assert!(!hidden(0).contains("d2h-file-list-hidden"));
+        assert!(hidden(1).contains("1 file with only whitespace changes hidden"));
+        assert!(hidden(3).contains("3 files with only whitespace changes hidden"));
+    }
+}
@@ -9,6 +9,7 @@ use crate::{config::Diff2HtmlConfig, parse};
 static GENERIC_COLUMN_LINE_NUMBER: &'static str =
     include_str!("../templates/generic-column-line-number.hbs");
 static GENERIC_EMPTY_DIFF: &'static str = include_str!("../templates/generic-empty-diff.hbs");
+static GENERIC_SUBMODULE_DIFF: &str = include_str!("../templates/generic-submodule-diff.hbs");
 static GENERIC_FILE_PATH: &'static str = include_str!("../templates/generic-file-path.hbs");
 static GENERIC_LINE: &'static str = include_str!("../templates/generic-line.hbs");
 static GENERIC_WRAPPER: &'static str = include_str!("../templates/generic-wrapper.hbs");
@@ -33,6 +34,9 @@ impl LineByLinePrinter {
         handlebars
             .register_template_string("generic-empty-diff", GENERIC_EMPTY_DIFF)
             .unwrap();
+        handlebars
+            .register_template_string("generic-submodule-diff", GENERIC_SUBMODULE_DIFF)
+            .unwrap();
         handlebars
             .register_template_string("generic-file-path", GENERIC_FILE_PATH)
             .unwrap();
@@ -50,24 +54,17 @@ impl LineByLinePrinter {
             .unwrap();
 
         LineByLinePrinter {
+            line_matcher: utils::get_line_matcher(&config),
+            diff_matcher: utils::get_difference_matcher(&config),
             config: config,
             handlebars: handlebars,
-            line_matcher: utils::get_line_matcher(),
-            diff_matcher: utils::get_difference_matcher(),
         }
     }
 
     pub fn render(&self, files: &Vec<parse::File>) -> String {
         let output = files
             .iter()
-            .map(|file| {
-                let diffs = if file.blocks.len() > 0 {
-                    self.generate_file_html(file)
-                } else {
-                    utils::generate_empty_diff(&self.handlebars, "d2h-code-side-line")
-                };
-                self.generate_file_diff_html(file, diffs)
-            })
+            .map(|file| self.render_file(file))
             .collect::<Vec<String>>()
             .join("\n");
 
@@ -81,6 +78,19 @@ impl LineByLinePrinter {
             .unwrap()
     }
 
+    /// Renders a single file without the surrounding wrapper, so files can be
+    /// printed as they are parsed.
+    pub fn render_file(&self, file: &parse::File) -> String {
+        let diffs = if file.submodule.is_some() {
+            utils::generate_submodule_diff(&self.handlebars, file, "d2h-code-line")
+        } else if !file.blocks.is_empty() {
+            self.generate_file_html(file)
+        } else {
+            utils::generate_empty_diff(&self.handlebars, "d2h-code-side-line")
+        };
+        self.generate_file_diff_html(file, diffs)
+    }
+
     fn generate_file_diff_html(&self, file: &parse::File, diffs: String) -> String {
         let file_path = self
             .handlebars
@@ -107,6 +117,7 @@ impl LineByLinePrinter {
     }
 
     fn generate_file_html(&self, file: &parse::File) -> String {
+        let prefix_size = utils::get_prefix_size(file);
         file.blocks
             .iter()
             .map(|block| {
@@ -124,6 +135,20 @@ impl LineByLinePrinter {
                     let line = &block.lines[i];
                     let escaped_line = escape(&line.content).to_string();
 
+                    // Word diffs tell which words changed, in a single line.
+                    if file.is_word_diff {
+                        lines += &self.generate_line_html(
+                            prefix_size,
+                            line.line_type.as_ref().unwrap(),
+                            line.old_number,
+                            line.new_number,
+                            utils::get_segments_html(&line.segments, None),
+                            Some(&line.content[0..prefix_size]),
+                            line.no_newline_at_eof,
+                        );
+                        continue;
+                    }
+
                     if line.line_type != Some(parse::LineType::Inserts)
                         && (new_lines.len() > 0
                             || (line.line_type != Some(parse::LineType::Deletes)
@@ -134,23 +159,25 @@ impl LineByLinePrinter {
 
                     if line.line_type == Some(parse::LineType::Context) {
                         lines += &self.generate_line_html(
-                            file.is_combined,
+                            prefix_size,
                             line.line_type.as_ref().unwrap(),
                             line.old_number,
                             line.new_number,
                             escaped_line,
                             None,
+                            line.no_newline_at_eof,
                         );
                     } else if line.line_type == Some(parse::LineType::Inserts)
                         && old_lines.len() == 0
                     {
                         lines += &self.generate_line_html(
-                            file.is_combined,
+                            prefix_size,
                             line.line_type.as_ref().unwrap(),
                             line.old_number,
                             line.new_number,
                             escaped_line,
                             None,
+                            line.no_newline_at_eof,
                         );
                     } else if line.line_type == Some(parse::LineType::Deletes) {
                         old_lines.push(line.to_owned());
@@ -179,6 +206,7 @@ impl LineByLinePrinter {
         old_lines: &mut Vec<parse::Line>,
         new_lines: &mut Vec<parse::Line>,
     ) {
+        let prefix_size = utils::get_prefix_size(file);
         let comparisons = old_lines.len() * new_lines.len();
         let max_comparisons = 2500;
         let do_matching = comparisons < max_comparisons && (self.config.matching != "none");
@@ -223,25 +251,28 @@ impl LineByLinePrinter {
                 let diff = utils::diff_highlight(
                     &self.config,
                     Some(&self.diff_matcher),
+                    prefix_size,
                     &old_line.as_ref().unwrap().content,
                     &new_line.as_ref().unwrap().content,
                 );
 
                 processed_old_lines += &self.generate_line_html(
-                    file.is_combined,
+                    prefix_size,
                     &delete_type,
                     old_line.as_ref().and_then(|v| v.old_number),
                     old_line.as_ref().and_then(|v| v.new_number),
                     diff.first.line,
                     Some(diff.first.prefix),
+                    old_line.is_some_and(|v| v.no_newline_at_eof),
                 );
                 processed_new_lines += &self.generate_line_html(
-                    file.is_combined,
+                    prefix_size,
                     &insert_type,
                     new_line.as_ref().and_then(|v| v.old_number),
                     new_line.as_ref().and_then(|v| v.new_number),
                     diff.second.line,
                     Some(diff.second.prefix),
+                    new_line.is_some_and(|v| v.no_newline_at_eof),
                 );
 
                 j += 1;
@@ -250,8 +281,7 @@ impl LineByLinePrinter {
             *lines += &processed_old_lines as &str;
             *lines += &processed_new_lines as &str;
 
-            *lines +=
-                &self.process_lines(file.is_combined, &old_lines[common..], &new_lines[common..]);
+            *lines += &self.process_lines(prefix_size, &old_lines[common..], &new_lines[common..]);
         });
 
         *old_lines = Vec::new();
@@ -260,12 +290,13 @@ impl LineByLinePrinter {
 
     fn generate_line_html(
         &self,
-        is_combined: bool,
+        prefix_size: usize,
         line_type: &parse::LineType,
         old_number: Option<usize>,
         new_number: Option<usize>,
         content: String,
         possible_prefix: Option<&str>,
+        no_newline: bool,
     ) -> String {
         let line_number = self
             .handlebars
@@ -281,7 +312,7 @@ impl LineByLinePrinter {
         let (prefix, line_without_prefix) = match possible_prefix {
             Some(prefix) => (prefix, content),
             _ => {
-                let line_with_prefix = utils::separate_prefix(is_combined, &content);
+                let line_with_prefix = utils::separate_prefix(prefix_size, &content);
                 (line_with_prefix.prefix, line_with_prefix.line.to_owned())
             }
         };
@@ -296,6 +327,7 @@ impl LineByLinePrinter {
                     "prefix": prefix.to_owned(),
                     "content": line_without_prefix,
                     "lineNumber": line_number,
+                    "noNewline": no_newline,
                 }),
             )
             .unwrap()
@@ -303,7 +335,7 @@ impl LineByLinePrinter {
 
     fn process_lines(
         &self,
-        is_combined: bool,
+        prefix_size: usize,
         old_lines: &[parse::Line],
         new_lines: &[parse::Line],
     ) -> String {
@@ -313,12 +345,13 @@ impl LineByLinePrinter {
             let old_line = &old_lines[i];
             let old_escaped_line = escape(&old_line.content);
             lines += &self.generate_line_html(
-                is_combined,
+                prefix_size,
                 old_line.line_type.as_ref().unwrap(),
                 old_line.old_number,
                 old_line.new_number,
                 old_escaped_line.to_string(),
                 None,
+                old_line.no_newline_at_eof,
             );
         }
 
@@ -326,15 +359,46 @@ impl LineByLinePrinter {
             let new_line = &new_lines[j];
             let new_escaped_line = escape(&new_line.content);
             lines += &self.generate_line_html(
-                is_combined,
+                prefix_size,
                 new_line.line_type.as_ref().unwrap(),
                 new_line.old_number,
                 new_line.new_number,
                 new_escaped_line.to_string(),
                 None,
+                new_line.no_newline_at_eof,
             );
         }
 
         lines
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn render(diff: &str) -> String {
+        LineByLinePrinter::new(Diff2HtmlConfig::default()).render(&parse::parse_diff(diff))
+    }
+
+    #[test]
+    fn marks_lines_without_a_newline() {
+        let html = render("--- a/x\n+++ b/x\n@@ -1 +1 @@\n-a\n\\ No newline at end of file\n+b\n");
+        assert_eq!(html.matches("d2h-no-newline").count(), 1);
+        // After the deleted line, before the inserted one.
+        let marker = html.find("d2h-no-newline").unwrap();
+        assert!(html.find("d2h-del").unwrap() < marker);
+        assert!(marker < html.find("d2h-ins").unwrap());
+
+        let html = render("--- a/x\n+++ b/x\n@@ -1 +1 @@\n-a\n+b\n");
+        assert!(!html.contains("d2h-no-newline"));
+    }
+
+    #[test]
+    fn marks_the_words_of_word_diff_lines() {
+        let html = render(
+            "diff --git a/x b/x\n--- a/x\n+++ b/x\n@@ -1 +1 @@\n let \n-a<b\n+c\n  = 1;\n~\n",
+        );
+        assert!(html.contains("let <del>a&lt;b</del><ins>c</ins> = 1;"));
+    }
+}
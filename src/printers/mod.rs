@@ -1,10 +1,14 @@
 mod file_list;
 mod line_by_line;
 mod page;
+mod range_diff;
 mod side_by_side;
+mod three_way;
 pub(crate) mod utils;
 
 pub use self::file_list::FileListPrinter;
 pub use self::line_by_line::LineByLinePrinter;
 pub use self::page::PagePrinter;
+pub use self::range_diff::RangeDiffPrinter;
 pub use self::side_by_side::SideBySidePrinter;
+pub use self::three_way::ThreeWayPrinter;
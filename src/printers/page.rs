@@ -1,33 +1,181 @@
+use std::io::{self, Write};
+
+use handlebars::Handlebars;
+
 use crate::config::Diff2HtmlConfig;
 use crate::parse;
-use crate::printers::{FileListPrinter, LineByLinePrinter, SideBySidePrinter};
+use crate::printers::{
+    FileListPrinter, LineByLinePrinter, RangeDiffPrinter, SideBySidePrinter, ThreeWayPrinter,
+};
 
 static CSS: &'static str = include_str!("../templates/css.hbs");
+static COMMIT_HEADER: &str = include_str!("../templates/commit-header.hbs");
+static COMMIT_TOC: &str = include_str!("../templates/commit-toc.hbs");
+static PAGE_FOOTER: &str = r#"
+                </body>
+            </html>
+        "#;
 
 pub struct PagePrinter {
     config: Diff2HtmlConfig,
+    handlebars: Handlebars,
 }
 
 impl PagePrinter {
     pub fn new(config: Diff2HtmlConfig) -> PagePrinter {
-        PagePrinter { config: config }
+        let mut handlebars = Handlebars::new();
+        handlebars
+            .register_template_string("commit-header", COMMIT_HEADER)
+            .unwrap();
+        handlebars
+            .register_template_string("commit-toc", COMMIT_TOC)
+            .unwrap();
+        PagePrinter { config, handlebars }
     }
 
     pub fn render(&self, files: &Vec<parse::File>) -> String {
+        self.render_page(&self.render_files(files))
+    }
+
+    /// Renders commit by commit, each header above its files, with a table of
+    /// contents when there is more than one commit.
+    pub fn render_commits(&self, commits: &[parse::Commit]) -> String {
+        let toc = if commits.len() > 1 {
+            self.handlebars
+                .render(
+                    "commit-toc",
+                    &json!({
+                        "commitsNumber": commits.len(),
+                        "commits": commits
+                            .iter()
+                            .enumerate()
+                            .map(|(i, commit)| json!({
+                                "commitHtmlId": get_commit_html_id(i, commit),
+                                "subject": commit.subject,
+                                "shortSha": commit.sha.as_ref().map(|sha| sha.chars().take(7).collect::<String>()),
+                            }))
+                            .collect::<Vec<_>>(),
+                    }),
+                )
+                .unwrap()
+        } else {
+            "".to_owned()
+        };
+
+        let content = commits
+            .iter()
+            .enumerate()
+            .map(|(i, commit)| {
+                let header = self
+                    .handlebars
+                    .render(
+                        "commit-header",
+                        &json!({
+                            "commitHtmlId": get_commit_html_id(i, commit),
+                            "sha": commit.sha,
+                            "author": commit.author,
+                            "date": commit.date,
+                            "subject": commit.subject,
+                            "message": commit.message,
+                        }),
+                    )
+                    .unwrap();
+                header + &self.render_files(&commit.files)
+            })
+            .collect::<Vec<String>>()
+            .join("\n");
+
+        self.render_page(&(toc + &content))
+    }
+
+    /// Renders the pairs of commits of `git range-diff` output.
+    pub fn render_range_diff(&self, pairs: &[parse::RangeDiffPair]) -> String {
+        self.render_page(&RangeDiffPrinter::new(self.config.to_owned()).render(pairs))
+    }
+
+    /// Renders files as they are read and writes each one out right away, so
+    /// only their summary lines are held in memory. The summary can only be
+    /// written after the last file, its styling shows it above them.
+    pub fn render_to<I, W>(&self, files: I, out: &mut W) -> io::Result<()>
+    where
+        I: Iterator<Item = parse::File>,
+        W: Write + ?Sized,
+    {
+        let render_file: Box<dyn Fn(&parse::File) -> String> = if self.config.style == "line" {
+            let printer = LineByLinePrinter::new(self.config.to_owned());
+            Box::new(move |file| printer.render_file(file))
+        } else if self.config.style == "three" {
+            let printer = ThreeWayPrinter::new(self.config.to_owned());
+            Box::new(move |file| printer.render_file(file))
+        } else {
+            let printer = SideBySidePrinter::new(self.config.to_owned());
+            Box::new(move |file| printer.render_file(file))
+        };
+
+        let mut summary_lines = Vec::new();
+        let mut hidden_files = 0;
+        let file_list = FileListPrinter::new();
+
+        write!(out, "{}", get_page_header())?;
+        writeln!(out, r#"<div class="d2h-stream">"#)?;
+        writeln!(out, r#"<div class="d2h-wrapper">"#)?;
+        for mut file in files {
+            if self.config.hide_whitespace_changes && parse::hide_file_whitespace_changes(&mut file)
+            {
+                hidden_files += 1;
+                continue;
+            }
+            if self.config.summary != "hidden" {
+                summary_lines.push(file_list.render_line(&file));
+            }
+            writeln!(out, "{}", render_file(&file))?;
+        }
+        writeln!(out, "</div>")?;
+        if self.config.summary != "hidden" {
+            let summary = file_list
+                .with_hidden_files(hidden_files)
+                .render_lines(&summary_lines);
+            writeln!(out, "{}", summary)?;
+        }
+        writeln!(out, "</div>")?;
+        write!(out, "{}", PAGE_FOOTER)
+    }
+
+    fn render_files(&self, files: &Vec<parse::File>) -> String {
+        let mut files = files.to_owned();
+        let hidden_files = if self.config.hide_whitespace_changes {
+            parse::hide_whitespace_changes(&mut files)
+        } else {
+            0
+        };
+
         let summary = if self.config.summary != "hidden" {
-            FileListPrinter::new().render(&files)
+            FileListPrinter::new()
+                .with_hidden_files(hidden_files)
+                .render(&files)
         } else {
             "".to_owned()
         };
 
         let content = if self.config.style == "line" {
             LineByLinePrinter::new(self.config.to_owned()).render(&files)
+        } else if self.config.style == "three" {
+            ThreeWayPrinter::new(self.config.to_owned()).render(&files)
         } else {
             SideBySidePrinter::new(self.config.to_owned()).render(&files)
         };
 
-        format!(
-            r#"
+        summary + &content
+    }
+
+    fn render_page(&self, content: &str) -> String {
+        get_page_header() + content + PAGE_FOOTER
+    }
+}
+
+fn get_page_header() -> String {
+    format!(
+        r#"
             <!DOCTYPE html>
             <html lang="en">
                 <head>
@@ -52,12 +200,14 @@ impl PagePrinter {
                     </style>
                 </head>
                 <body>
-                    {}
-                    {}
-                </body>
-            </html>
-        "#,
-            CSS, &summary, &content
-        )
+                    "#,
+        CSS
+    )
+}
+
+fn get_commit_html_id(index: usize, commit: &parse::Commit) -> String {
+    match commit.sha {
+        Some(ref sha) => format!("d2h-commit-{}", sha),
+        _ => format!("d2h-commit-{}", index),
     }
 }
@@ -0,0 +1,194 @@
+use handlebars::Handlebars;
+
+use super::utils;
+use crate::{config::Diff2HtmlConfig, parse};
+
+static RANGE_DIFF_LIST: &str = include_str!("../templates/range-diff-list.hbs");
+static RANGE_DIFF_PAIR: &str = include_str!("../templates/range-diff-pair.hbs");
+static RANGE_DIFF_LINE: &str = include_str!("../templates/range-diff-line.hbs");
+
+pub struct RangeDiffPrinter {
+    config: Diff2HtmlConfig,
+    handlebars: Handlebars,
+}
+
+impl RangeDiffPrinter {
+    pub fn new(config: Diff2HtmlConfig) -> RangeDiffPrinter {
+        let mut handlebars = Handlebars::new();
+        handlebars
+            .register_template_string("range-diff-list", RANGE_DIFF_LIST)
+            .unwrap();
+        handlebars
+            .register_template_string("range-diff-pair", RANGE_DIFF_PAIR)
+            .unwrap();
+        handlebars
+            .register_template_string("range-diff-line", RANGE_DIFF_LINE)
+            .unwrap();
+        RangeDiffPrinter { config, handlebars }
+    }
+
+    /// Renders the list of pairs, then every pair with the diff between its
+    /// patches.
+    pub fn render(&self, pairs: &[parse::RangeDiffPair]) -> String {
+        let list = if self.config.summary != "hidden" {
+            self.handlebars
+                .render(
+                    "range-diff-list",
+                    &json!({
+                        "pairsNumber": pairs.len(),
+                        "pairs": pairs
+                            .iter()
+                            .enumerate()
+                            .map(|(i, pair)| json!({
+                                "pairHtmlId": get_pair_html_id(i),
+                                "oldSha": pair.old_sha.as_ref().map_or("-------", |sha| sha.as_str()),
+                                "newSha": pair.new_sha.as_ref().map_or("-------", |sha| sha.as_str()),
+                                "subject": pair.subject,
+                                "statusTag": get_status_tag(&pair.status),
+                            }))
+                            .collect::<Vec<_>>(),
+                    }),
+                )
+                .unwrap()
+        } else {
+            "".to_owned()
+        };
+
+        let content = pairs
+            .iter()
+            .enumerate()
+            .map(|(i, pair)| self.render_pair(i, pair))
+            .collect::<Vec<String>>()
+            .join("\n");
+
+        list + &content
+    }
+
+    fn render_pair(&self, index: usize, pair: &parse::RangeDiffPair) -> String {
+        let lines = pair
+            .lines
+            .iter()
+            .map(|line| self.render_line(line))
+            .collect::<Vec<String>>()
+            .join("\n");
+
+        self.handlebars
+            .render(
+                "range-diff-pair",
+                &json!({
+                    "pairHtmlId": get_pair_html_id(index),
+                    "oldIndex": pair.old_index.map_or("-".to_owned(), |v| v.to_string()),
+                    "oldSha": pair.old_sha.as_ref().map_or("-------", |sha| sha.as_str()),
+                    "newIndex": pair.new_index.map_or("-".to_owned(), |v| v.to_string()),
+                    "newSha": pair.new_sha.as_ref().map_or("-------", |sha| sha.as_str()),
+                    "subject": pair.subject,
+                    "statusTag": get_status_tag(&pair.status),
+                    "lines": lines,
+                }),
+            )
+            .unwrap()
+    }
+
+    // The outer marker colors the whole line, the inner one only its text.
+    fn render_line(&self, line: &parse::RangeDiffLine) -> String {
+        let outer_class = line.outer.as_ref().map_or("d2h-info", |line_type| {
+            utils::get_line_type_class(line_type)
+        });
+        let inner_class = match line.inner {
+            Some(parse::LineType::Inserts) => "d2h-range-diff-inner-ins",
+            Some(parse::LineType::Deletes) => "d2h-range-diff-inner-del",
+            _ => "",
+        };
+
+        self.handlebars
+            .render(
+                "range-diff-line",
+                &json!({
+                    "outerClass": outer_class,
+                    "outerPrefix": line.outer.as_ref().map_or("", get_prefix),
+                    "innerClass": inner_class,
+                    "innerPrefix": line.inner.as_ref().map_or("", get_prefix),
+                    "content": line.content,
+                }),
+            )
+            .unwrap()
+    }
+}
+
+fn get_pair_html_id(index: usize) -> String {
+    format!("d2h-range-diff-{}", index)
+}
+
+fn get_prefix(line_type: &parse::LineType) -> &'static str {
+    match line_type {
+        parse::LineType::Inserts | parse::LineType::InsertChanges => "+",
+        parse::LineType::Deletes | parse::LineType::DeleteChanges => "-",
+        parse::LineType::Context => " ",
+    }
+}
+
+fn get_status_tag(status: &parse::RangeDiffStatus) -> &'static str {
+    match status {
+        parse::RangeDiffStatus::Equal => {
+            r#"<span class="d2h-tag d2h-equal d2h-equal-tag">EQUAL</span>"#
+        }
+        parse::RangeDiffStatus::Modified => {
+            r#"<span class="d2h-tag d2h-changed d2h-changed-tag">MODIFIED</span>"#
+        }
+        parse::RangeDiffStatus::Added => {
+            r#"<span class="d2h-tag d2h-added d2h-added-tag">ADDED</span>"#
+        }
+        parse::RangeDiffStatus::Dropped => {
+            r#"<span class="d2h-tag d2h-deleted d2h-deleted-tag">DROPPED</span>"#
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    static RANGE_DIFF: &str = "1:  a8042b7 = 1:  c3f1d2e Change one
+2:  d0480e1 ! 2:  b1e2f3a Change <two>
+    @@ src/x.rs
+    --    a
+    ++    c
+-:  ------- > 3:  89abcde Change four
+";
+
+    fn render(config: Diff2HtmlConfig) -> String {
+        RangeDiffPrinter::new(config).render(&parse::parse_range_diff(RANGE_DIFF))
+    }
+
+    #[test]
+    fn renders_pairs_with_their_status() {
+        let html = render(Diff2HtmlConfig::default());
+        assert!(html.contains("EQUAL"));
+        assert!(html.contains("MODIFIED"));
+        assert!(html.contains("ADDED"));
+        assert!(html.contains("Change &lt;two&gt;"));
+        assert!(html.contains("d2h-range-diff-2"));
+        assert!(html.contains("-------"));
+    }
+
+    #[test]
+    fn marks_outer_and_inner_changes() {
+        let html = render(Diff2HtmlConfig::default());
+        assert!(html.contains("d2h-range-diff-inner d2h-range-diff-inner-del\">-"));
+        assert!(html.contains("d2h-range-diff-inner d2h-range-diff-inner-ins\">+"));
+        assert!(html.contains("d2h-code-line d2h-del"));
+        assert!(html.contains("d2h-code-line d2h-ins"));
+        assert!(html.contains("d2h-code-line d2h-info"));
+    }
+
+    #[test]
+    fn hides_the_list_with_the_summary() {
+        let config = Diff2HtmlConfig {
+            summary: "hidden".to_owned(),
+            ..Diff2HtmlConfig::default()
+        };
+        let hidden = render(config);
+        assert!(render(Diff2HtmlConfig::default()).len() > hidden.len());
+        assert!(hidden.contains("d2h-range-diff-0"));
+    }
+}
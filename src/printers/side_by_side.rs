@@ -9,6 +9,7 @@ use crate::{config::Diff2HtmlConfig, parse};
 static GENERIC_COLUMN_LINE_NUMBER: &'static str =
     include_str!("../templates/generic-column-line-number.hbs");
 static GENERIC_EMPTY_DIFF: &'static str = include_str!("../templates/generic-empty-diff.hbs");
+static GENERIC_SUBMODULE_DIFF: &str = include_str!("../templates/generic-submodule-diff.hbs");
 static GENERIC_FILE_PATH: &'static str = include_str!("../templates/generic-file-path.hbs");
 static GENERIC_LINE: &'static str = include_str!("../templates/generic-line.hbs");
 static GENERIC_WRAPPER: &'static str = include_str!("../templates/generic-wrapper.hbs");
@@ -32,6 +33,9 @@ impl SideBySidePrinter {
         handlebars
             .register_template_string("generic-empty-diff", GENERIC_EMPTY_DIFF)
             .unwrap();
+        handlebars
+            .register_template_string("generic-submodule-diff", GENERIC_SUBMODULE_DIFF)
+            .unwrap();
         handlebars
             .register_template_string("generic-file-path", GENERIC_FILE_PATH)
             .unwrap();
@@ -46,24 +50,17 @@ impl SideBySidePrinter {
             .unwrap();
 
         SideBySidePrinter {
+            line_matcher: utils::get_line_matcher(&config),
+            diff_matcher: utils::get_difference_matcher(&config),
             config: config,
             handlebars: handlebars,
-            line_matcher: utils::get_line_matcher(),
-            diff_matcher: utils::get_difference_matcher(),
         }
     }
 
     pub fn render(&self, files: &Vec<parse::File>) -> String {
         let output = files
             .iter()
-            .map(|file| {
-                let diffs = if file.blocks.len() > 0 {
-                    self.generate_file_html(file)
-                } else {
-                    self.generate_empty_diff()
-                };
-                self.make_file_diff_html(file, diffs)
-            })
+            .map(|file| self.render_file(file))
             .collect::<Vec<String>>()
             .join("\n");
 
@@ -77,6 +74,19 @@ impl SideBySidePrinter {
             .unwrap()
     }
 
+    /// Renders a single file without the surrounding wrapper, so files can be
+    /// printed as they are parsed.
+    pub fn render_file(&self, file: &parse::File) -> String {
+        let diffs = if file.submodule.is_some() {
+            self.generate_submodule_diff(file)
+        } else if !file.blocks.is_empty() {
+            self.generate_file_html(file)
+        } else {
+            self.generate_empty_diff()
+        };
+        self.make_file_diff_html(file, diffs)
+    }
+
     fn make_file_diff_html(&self, file: &parse::File, diffs: SideBySideFile) -> String {
         let file_path = self
             .handlebars
@@ -105,6 +115,7 @@ impl SideBySidePrinter {
 
     fn generate_file_html(&self, file: &parse::File) -> SideBySideFile {
         let mut file_html = SideBySideFile::new();
+        let prefix_size = utils::get_prefix_size(file);
 
         file.blocks.iter().for_each(|block| {
             file_html.left += &utils::make_column_line_number_html(
@@ -125,8 +136,13 @@ impl SideBySidePrinter {
 
             for i in 0..block.lines.len() {
                 let line = &block.lines[i];
-                let prefix = &line.content[0..1];
-                let escaped_line = escape(&line.content[1..]).to_string();
+                let prefix = &line.content[0..prefix_size];
+                let escaped_line = escape(&line.content[prefix_size..]).to_string();
+
+                if file.is_word_diff {
+                    self.generate_word_line_html(prefix_size, &mut file_html, line);
+                    continue;
+                }
 
                 if line.line_type != Some(parse::LineType::Inserts)
                     && (new_lines.len() > 0
@@ -138,33 +154,37 @@ impl SideBySidePrinter {
 
                 if line.line_type == Some(parse::LineType::Context) {
                     file_html.left += &self.generate_line_html(
-                        file.is_combined,
+                        prefix_size,
                         &line.line_type.as_ref().unwrap(),
                         line.old_number,
                         escaped_line.to_owned(),
                         Some(prefix),
+                        line.no_newline_at_eof,
                     );
                     file_html.right += &self.generate_line_html(
-                        file.is_combined,
+                        prefix_size,
                         &line.line_type.as_ref().unwrap(),
                         line.new_number,
                         escaped_line.to_owned(),
                         Some(prefix),
+                        line.no_newline_at_eof,
                     );
                 } else if line.line_type == Some(parse::LineType::Inserts) && old_lines.len() == 0 {
                     file_html.left += &self.generate_line_html(
-                        file.is_combined,
+                        prefix_size,
                         &parse::LineType::Context,
                         None,
                         "".to_owned(),
                         None,
+                        false,
                     );
                     file_html.right += &self.generate_line_html(
-                        file.is_combined,
+                        prefix_size,
                         &line.line_type.as_ref().unwrap(),
                         line.new_number,
                         escaped_line.to_owned(),
                         Some(prefix),
+                        line.no_newline_at_eof,
                     );
                 } else if line.line_type == Some(parse::LineType::Deletes) {
                     old_lines.push(line.to_owned());
@@ -182,6 +202,67 @@ impl SideBySidePrinter {
         file_html
     }
 
+    /*
+     * A word diff line is shown with its old words on the left and its new
+     * ones on the right. Lines with both are changed lines.
+     */
+    fn generate_word_line_html(
+        &self,
+        prefix_size: usize,
+        file_html: &mut SideBySideFile,
+        line: &parse::Line,
+    ) {
+        let is_changed = line
+            .segments
+            .iter()
+            .any(|segment| segment.line_type != parse::LineType::Context);
+        let (left_type, right_type) = match line.line_type {
+            Some(parse::LineType::Inserts) => (None, parse::LineType::Inserts),
+            Some(parse::LineType::Deletes) => {
+                (Some(parse::LineType::Deletes), parse::LineType::Context)
+            }
+            _ if is_changed => (
+                Some(parse::LineType::DeleteChanges),
+                parse::LineType::InsertChanges,
+            ),
+            _ => (Some(parse::LineType::Context), parse::LineType::Context),
+        };
+
+        let left = match left_type {
+            Some(line_type) => self.generate_line_html(
+                prefix_size,
+                &line_type,
+                line.old_number,
+                utils::get_segments_html(&line.segments, Some(&parse::LineType::Inserts)),
+                Some(if is_changed { "-" } else { " " }),
+                line.no_newline_at_eof,
+            ),
+            None => self.generate_line_html(
+                prefix_size,
+                &parse::LineType::Context,
+                None,
+                "".to_owned(),
+                None,
+                false,
+            ),
+        };
+        let right = if line.line_type == Some(parse::LineType::Deletes) {
+            self.generate_line_html(prefix_size, &right_type, None, "".to_owned(), None, false)
+        } else {
+            self.generate_line_html(
+                prefix_size,
+                &right_type,
+                line.new_number,
+                utils::get_segments_html(&line.segments, Some(&parse::LineType::Deletes)),
+                Some(if is_changed { "+" } else { " " }),
+                line.no_newline_at_eof,
+            )
+        };
+
+        file_html.left += &left;
+        file_html.right += &right;
+    }
+
     fn process_change_block(
         &self,
         file: &parse::File,
@@ -189,6 +270,7 @@ impl SideBySidePrinter {
         old_lines: &mut Vec<parse::Line>,
         new_lines: &mut Vec<parse::Line>,
     ) {
+        let prefix_size = utils::get_prefix_size(file);
         let comparisons = old_lines.len() * new_lines.len();
         let max_comparisons = 2500;
         let do_matching = comparisons < max_comparisons && (self.config.matching != "none");
@@ -228,23 +310,26 @@ impl SideBySidePrinter {
                 let diff = utils::diff_highlight(
                     &self.config,
                     Some(&self.diff_matcher),
+                    prefix_size,
                     &old_line.as_ref().unwrap().content,
                     &new_line.as_ref().unwrap().content,
                 );
 
                 file_html.left += &self.generate_line_html(
-                    file.is_combined,
+                    prefix_size,
                     &delete_type,
                     old_line.as_ref().and_then(|v| v.old_number),
                     diff.first.line,
                     Some(diff.first.prefix),
+                    old_line.is_some_and(|v| v.no_newline_at_eof),
                 );
                 file_html.right += &self.generate_line_html(
-                    file.is_combined,
+                    prefix_size,
                     &insert_type,
                     new_line.as_ref().and_then(|v| v.new_number),
                     diff.second.line,
                     Some(diff.second.prefix),
+                    new_line.is_some_and(|v| v.no_newline_at_eof),
                 );
 
                 j += 1;
@@ -254,7 +339,7 @@ impl SideBySidePrinter {
                 let old_slice = &old_lines[common..];
                 let new_slice = &new_lines[common..];
 
-                let html = self.process_lines(file.is_combined, &old_slice, &new_slice);
+                let html = self.process_lines(prefix_size, old_slice, new_slice);
                 file_html.left += &html.left;
                 file_html.right += &html.right;
             }
@@ -266,7 +351,7 @@ impl SideBySidePrinter {
 
     fn process_lines(
         &self,
-        is_combined: bool,
+        prefix_size: usize,
         old_lines: &[parse::Line],
         new_lines: &[parse::Line],
     ) -> SideBySideFile {
@@ -283,62 +368,71 @@ impl SideBySidePrinter {
             let mut new_prefix = None;
 
             if let Some(old_line) = old_line {
-                old_content = Some(escape(&old_line.content[1..]).to_string());
-                old_prefix = Some(&old_line.content[0..1])
+                old_content = Some(escape(&old_line.content[prefix_size..]).to_string());
+                old_prefix = Some(&old_line.content[0..prefix_size])
             }
 
             if let Some(new_line) = new_line {
-                new_content = Some(escape(&new_line.content[1..]).to_string());
-                new_prefix = Some(&new_line.content[0..1])
+                new_content = Some(escape(&new_line.content[prefix_size..]).to_string());
+                new_prefix = Some(&new_line.content[0..prefix_size])
             }
 
-            if old_line.is_some() && new_line.is_some() {
-                file_html.left += &self.generate_line_html(
-                    is_combined,
-                    old_line.as_ref().unwrap().line_type.as_ref().unwrap(),
-                    old_line.as_ref().unwrap().old_number,
-                    old_content.unwrap(),
-                    old_prefix,
-                );
-                file_html.right += &self.generate_line_html(
-                    is_combined,
-                    new_line.as_ref().unwrap().line_type.as_ref().unwrap(),
-                    new_line.as_ref().unwrap().old_number,
-                    new_content.unwrap(),
-                    new_prefix,
-                );
-            } else if old_line.is_some() {
-                file_html.left += &self.generate_line_html(
-                    is_combined,
-                    old_line.as_ref().unwrap().line_type.as_ref().unwrap(),
-                    old_line.as_ref().unwrap().old_number,
-                    old_content.unwrap(),
-                    old_prefix,
-                );
-                file_html.right += &self.generate_line_html(
-                    is_combined,
-                    &parse::LineType::Context,
-                    None,
-                    "".to_owned(),
-                    None,
-                );
-            } else if new_line.is_some() {
-                file_html.left += &self.generate_line_html(
-                    is_combined,
-                    &parse::LineType::Context,
-                    None,
-                    "".to_owned(),
-                    None,
-                );
-                file_html.right += &self.generate_line_html(
-                    is_combined,
-                    new_line.as_ref().unwrap().line_type.as_ref().unwrap(),
-                    new_line.as_ref().unwrap().new_number,
-                    new_content.unwrap(),
-                    new_prefix,
-                );
-            } else {
-                eprintln!("Unknown path.");
+            match (old_line, new_line) {
+                (Some(old_line), Some(new_line)) => {
+                    file_html.left += &self.generate_line_html(
+                        prefix_size,
+                        old_line.line_type.as_ref().unwrap(),
+                        old_line.old_number,
+                        old_content.unwrap(),
+                        old_prefix,
+                        old_line.no_newline_at_eof,
+                    );
+                    file_html.right += &self.generate_line_html(
+                        prefix_size,
+                        new_line.line_type.as_ref().unwrap(),
+                        new_line.old_number,
+                        new_content.unwrap(),
+                        new_prefix,
+                        new_line.no_newline_at_eof,
+                    );
+                }
+                (Some(old_line), None) => {
+                    file_html.left += &self.generate_line_html(
+                        prefix_size,
+                        old_line.line_type.as_ref().unwrap(),
+                        old_line.old_number,
+                        old_content.unwrap(),
+                        old_prefix,
+                        old_line.no_newline_at_eof,
+                    );
+                    file_html.right += &self.generate_line_html(
+                        prefix_size,
+                        &parse::LineType::Context,
+                        None,
+                        "".to_owned(),
+                        None,
+                        false,
+                    );
+                }
+                (None, Some(new_line)) => {
+                    file_html.left += &self.generate_line_html(
+                        prefix_size,
+                        &parse::LineType::Context,
+                        None,
+                        "".to_owned(),
+                        None,
+                        false,
+                    );
+                    file_html.right += &self.generate_line_html(
+                        prefix_size,
+                        new_line.line_type.as_ref().unwrap(),
+                        new_line.new_number,
+                        new_content.unwrap(),
+                        new_prefix,
+                        new_line.no_newline_at_eof,
+                    );
+                }
+                (None, None) => eprintln!("Unknown path."),
             }
         }
 
@@ -347,11 +441,12 @@ impl SideBySidePrinter {
 
     fn generate_line_html(
         &self,
-        is_combined: bool,
+        prefix_size: usize,
         line_type: &parse::LineType,
         number: Option<usize>,
         content: String,
         possible_prefix: Option<&str>,
+        no_newline: bool,
     ) -> String {
         let mut line_class = "d2h-code-side-linenumber".to_owned();
         let mut content_class = "d2h-code-side-line".to_owned();
@@ -366,7 +461,7 @@ impl SideBySidePrinter {
         let (prefix, line_without_prefix) = match possible_prefix {
             Some(prefix) => (prefix, content),
             _ => {
-                let line_with_prefix = utils::separate_prefix(is_combined, &content);
+                let line_with_prefix = utils::separate_prefix(prefix_size, &content);
                 (line_with_prefix.prefix, line_with_prefix.line.to_owned())
             }
         };
@@ -381,6 +476,7 @@ impl SideBySidePrinter {
                     "prefix": prefix.to_owned(),
                     "content": line_without_prefix,
                     "lineNumber": number,
+                    "noNewline": no_newline,
                 }),
             )
             .unwrap()
@@ -391,6 +487,13 @@ impl SideBySidePrinter {
         file_html.left += &utils::generate_empty_diff(&self.handlebars, "d2h-code-line");
         file_html
     }
+
+    fn generate_submodule_diff(&self, file: &parse::File) -> SideBySideFile {
+        let mut file_html = SideBySideFile::new();
+        file_html.left +=
+            &utils::generate_submodule_diff(&self.handlebars, file, "d2h-code-side-line");
+        file_html
+    }
 }
 
 #[derive(Debug, Serialize)]
@@ -407,3 +510,31 @@ impl SideBySideFile {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn render(diff: &str) -> String {
+        SideBySidePrinter::new(Diff2HtmlConfig::default()).render(&parse::parse_diff(diff))
+    }
+
+    // The html of the left and the right side.
+    fn sides(html: &str) -> (&str, &str) {
+        let right = html.rfind("d2h-file-side-diff").unwrap();
+        (&html[..right], &html[right..])
+    }
+
+    #[test]
+    fn marks_lines_without_a_newline_on_their_side() {
+        let html = render("--- a/x\n+++ b/x\n@@ -1 +1 @@\n-a\n\\ No newline at end of file\n+b\n");
+        let (left, right) = sides(&html);
+        assert!(left.contains("d2h-no-newline"));
+        assert!(!right.contains("d2h-no-newline"));
+
+        let html = render("--- a/x\n+++ b/x\n@@ -1 +1 @@\n-a\n+b\n\\ No newline at end of file\n");
+        let (left, right) = sides(&html);
+        assert!(!left.contains("d2h-no-newline"));
+        assert!(right.contains("d2h-no-newline"));
+    }
+}
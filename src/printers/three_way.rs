@@ -0,0 +1,436 @@
+use handlebars::Handlebars;
+use v_htmlescape::escape;
+
+use super::side_by_side::SideBySidePrinter;
+use super::utils::{self, rematch, Difference};
+use crate::{config::Diff2HtmlConfig, parse};
+
+static GENERIC_COLUMN_LINE_NUMBER: &str =
+    include_str!("../templates/generic-column-line-number.hbs");
+static GENERIC_FILE_PATH: &str = include_str!("../templates/generic-file-path.hbs");
+static GENERIC_LINE: &str = include_str!("../templates/generic-line.hbs");
+static GENERIC_WRAPPER: &str = include_str!("../templates/generic-wrapper.hbs");
+static THREE_WAY_FILE_DIFF: &str = include_str!("../templates/three-way-file-diff.hbs");
+static ICON_FILE: &str = include_str!("../templates/icon-file.hbs");
+
+// Panes are first parent, second parent and merge result, in that order.
+const RESULT: usize = 2;
+
+pub struct ThreeWayPrinter {
+    config: Diff2HtmlConfig,
+    handlebars: Handlebars,
+    diff_matcher: rematch::Rematcher<Difference>,
+    side_by_side: SideBySidePrinter,
+}
+
+impl ThreeWayPrinter {
+    pub fn new(config: Diff2HtmlConfig) -> ThreeWayPrinter {
+        let mut handlebars = Handlebars::new();
+        handlebars
+            .register_template_string("generic-column-line-number", GENERIC_COLUMN_LINE_NUMBER)
+            .unwrap();
+        handlebars
+            .register_template_string("generic-file-path", GENERIC_FILE_PATH)
+            .unwrap();
+        handlebars
+            .register_template_string("generic-line", GENERIC_LINE)
+            .unwrap();
+        handlebars
+            .register_template_string("generic-wrapper", GENERIC_WRAPPER)
+            .unwrap();
+        handlebars
+            .register_template_string("three-way-file-diff", THREE_WAY_FILE_DIFF)
+            .unwrap();
+
+        ThreeWayPrinter {
+            config: config.to_owned(),
+            handlebars,
+            diff_matcher: utils::get_difference_matcher(&config),
+            side_by_side: SideBySidePrinter::new(config),
+        }
+    }
+
+    pub fn render(&self, files: &[parse::File]) -> String {
+        let output = files
+            .iter()
+            .map(|file| self.render_file(file))
+            .collect::<Vec<String>>()
+            .join("\n");
+
+        self.handlebars
+            .render(
+                "generic-wrapper",
+                &json!({
+                    "content": output,
+                }),
+            )
+            .unwrap()
+    }
+
+    /// Renders a single file without the surrounding wrapper. Files that are
+    /// not two-parent merges are rendered side by side.
+    pub fn render_file(&self, file: &parse::File) -> String {
+        if !is_three_way(file) {
+            return self.side_by_side.render_file(file);
+        }
+
+        let diffs = self.generate_file_html(file);
+        self.make_file_diff_html(file, diffs)
+    }
+
+    fn make_file_diff_html(&self, file: &parse::File, diffs: ThreeWayFile) -> String {
+        let file_path = self
+            .handlebars
+            .render(
+                "generic-file-path",
+                &json!({
+                    "fileDiffName": utils::get_diff_name(file),
+                    "fileIcon": ICON_FILE,
+                    "fileTag": utils::get_line_type_tag(file).to_owned()
+                        + utils::get_resolution_tag(file),
+                }),
+            )
+            .unwrap();
+
+        self.handlebars
+            .render(
+                "three-way-file-diff",
+                &json!({
+                    "file": file.to_owned(),
+                    "fileHtmlId": utils::get_html_id(file),
+                    "diffs": diffs,
+                    "filePath": file_path,
+                }),
+            )
+            .unwrap()
+    }
+
+    fn generate_file_html(&self, file: &parse::File) -> ThreeWayFile {
+        let mut file_html = ThreeWayFile::new();
+        let prefix_size = utils::get_prefix_size(file);
+
+        file.blocks.iter().for_each(|block| {
+            for pane in 0..3 {
+                *file_html.pane(pane) += &utils::make_column_line_number_html(
+                    &self.handlebars,
+                    if pane == 0 {
+                        block.header.as_ref().unwrap()
+                    } else {
+                        ""
+                    },
+                    "d2h-code-side-linenumber",
+                    "d2h-code-side-line",
+                );
+            }
+
+            let mut panes: [Vec<Option<&parse::Line>>; 3] = [Vec::new(), Vec::new(), Vec::new()];
+
+            for line in block.lines.iter() {
+                if line.line_type == Some(parse::LineType::Context) {
+                    self.process_change_block(prefix_size, &mut file_html, &mut panes);
+                    for pane in 0..3 {
+                        *file_html.pane(pane) += &self.generate_pane_line_html(
+                            prefix_size,
+                            pane,
+                            line,
+                            escape(&line.content[prefix_size..]).to_string(),
+                            &parse::LineType::Context,
+                        );
+                    }
+                    continue;
+                }
+
+                // A line shared by several panes starts on the same row in all of them.
+                let shared = (0..3)
+                    .filter(|&pane| is_in_pane(line, pane))
+                    .collect::<Vec<usize>>();
+                let row = shared.iter().map(|&pane| panes[pane].len()).max();
+                for &pane in shared.iter() {
+                    panes[pane].resize(row.unwrap(), None);
+                    panes[pane].push(Some(line));
+                }
+            }
+
+            self.process_change_block(prefix_size, &mut file_html, &mut panes);
+        });
+
+        file_html
+    }
+
+    fn process_change_block(
+        &self,
+        prefix_size: usize,
+        file_html: &mut ThreeWayFile,
+        panes: &mut [Vec<Option<&parse::Line>>; 3],
+    ) {
+        let rows = panes.iter().map(|pane| pane.len()).max().unwrap();
+        for pane in panes.iter_mut() {
+            pane.resize(rows, None);
+        }
+
+        for (row, &result) in panes[RESULT].iter().enumerate() {
+            for (parent, pane) in panes.iter().enumerate().take(RESULT) {
+                let html = match pane[row] {
+                    Some(line) if line.parents[parent].line_type == parse::LineType::Deletes => {
+                        match result
+                            .filter(|v| v.parents[parent].line_type == parse::LineType::Inserts)
+                        {
+                            Some(result) => {
+                                let diff = self.highlight(prefix_size, line, result);
+                                self.generate_pane_line_html(
+                                    prefix_size,
+                                    parent,
+                                    line,
+                                    diff.first.line,
+                                    &parse::LineType::DeleteChanges,
+                                )
+                            }
+                            None => self.generate_pane_line_html(
+                                prefix_size,
+                                parent,
+                                line,
+                                escape(&line.content[prefix_size..]).to_string(),
+                                &parse::LineType::Deletes,
+                            ),
+                        }
+                    }
+                    Some(line) => self.generate_pane_line_html(
+                        prefix_size,
+                        parent,
+                        line,
+                        escape(&line.content[prefix_size..]).to_string(),
+                        &parse::LineType::Context,
+                    ),
+                    None => self.generate_empty_line_html(),
+                };
+                *file_html.pane(parent) += &html;
+            }
+
+            let html = match result {
+                Some(line) if line.line_type == Some(parse::LineType::Inserts) => {
+                    // Highlight against every parent the line was changed from.
+                    let against = (0..RESULT)
+                        .filter(|&parent| {
+                            line.parents[parent].line_type == parse::LineType::Inserts
+                        })
+                        .filter_map(|parent| {
+                            panes[parent][row]
+                                .filter(|v| v.parents[parent].line_type == parse::LineType::Deletes)
+                        })
+                        .map(|old_line| old_line.content.as_str())
+                        .collect::<Vec<&str>>();
+                    if against.is_empty() {
+                        self.generate_pane_line_html(
+                            prefix_size,
+                            RESULT,
+                            line,
+                            escape(&line.content[prefix_size..]).to_string(),
+                            &parse::LineType::Inserts,
+                        )
+                    } else {
+                        let content = utils::diff_highlight_all(
+                            &self.config,
+                            Some(&self.diff_matcher),
+                            prefix_size,
+                            &against,
+                            &line.content,
+                        );
+                        self.generate_pane_line_html(
+                            prefix_size,
+                            RESULT,
+                            line,
+                            content,
+                            &parse::LineType::InsertChanges,
+                        )
+                    }
+                }
+                Some(line) => self.generate_pane_line_html(
+                    prefix_size,
+                    RESULT,
+                    line,
+                    escape(&line.content[prefix_size..]).to_string(),
+                    &parse::LineType::Context,
+                ),
+                None => self.generate_empty_line_html(),
+            };
+            *file_html.pane(RESULT) += &html;
+        }
+
+        for pane in panes.iter_mut() {
+            pane.clear();
+        }
+    }
+
+    fn highlight<'a>(
+        &self,
+        prefix_size: usize,
+        old_line: &'a parse::Line,
+        new_line: &'a parse::Line,
+    ) -> utils::Highlighted<'a> {
+        utils::diff_highlight(
+            &self.config,
+            Some(&self.diff_matcher),
+            prefix_size,
+            &old_line.content,
+            &new_line.content,
+        )
+    }
+
+    // Parent panes show only their own prefix column, the result pane shows all of them.
+    fn generate_pane_line_html(
+        &self,
+        prefix_size: usize,
+        pane: usize,
+        line: &parse::Line,
+        content: String,
+        line_type: &parse::LineType,
+    ) -> String {
+        let (number, prefix) = if pane == RESULT {
+            (line.new_number, &line.content[0..prefix_size])
+        } else {
+            (line.parents[pane].old_number, &line.content[pane..pane + 1])
+        };
+
+        self.generate_line_html(line_type, number, content, prefix, line.no_newline_at_eof)
+    }
+
+    fn generate_empty_line_html(&self) -> String {
+        self.generate_line_html(&parse::LineType::Context, None, "".to_owned(), "", false)
+    }
+
+    fn generate_line_html(
+        &self,
+        line_type: &parse::LineType,
+        number: Option<usize>,
+        content: String,
+        prefix: &str,
+        no_newline: bool,
+    ) -> String {
+        let mut line_class = "d2h-code-side-linenumber".to_owned();
+        let mut content_class = "d2h-code-side-line".to_owned();
+        let mut line_type = utils::get_line_type_class(line_type).to_owned();
+
+        if number.is_none() && content.is_empty() {
+            line_class += " d2h-code-side-emptyplaceholder";
+            content_class += " d2h-code-side-emptyplaceholder";
+            line_type += " d2h-emptyplaceholder";
+        }
+
+        self.handlebars
+            .render(
+                "generic-line",
+                &json!({
+                    "type": line_type,
+                    "lineClass": line_class,
+                    "contentClass": content_class,
+                    "prefix": prefix.to_owned(),
+                    "content": content,
+                    "lineNumber": number,
+                    "noNewline": no_newline,
+                }),
+            )
+            .unwrap()
+    }
+}
+
+fn is_three_way(file: &parse::File) -> bool {
+    !file.blocks.is_empty()
+        && file
+            .blocks
+            .iter()
+            .all(|block| block.parent_ranges.len() == RESULT)
+}
+
+fn is_in_pane(line: &parse::Line, pane: usize) -> bool {
+    if pane == RESULT {
+        line.new_number.is_some()
+    } else {
+        line.parents[pane].old_number.is_some()
+    }
+}
+
+#[derive(Debug, Serialize)]
+pub struct ThreeWayFile {
+    pub parent1: String,
+    pub parent2: String,
+    pub result: String,
+}
+
+impl ThreeWayFile {
+    fn new() -> ThreeWayFile {
+        ThreeWayFile {
+            parent1: String::new(),
+            parent2: String::new(),
+            result: String::new(),
+        }
+    }
+
+    fn pane(&mut self, pane: usize) -> &mut String {
+        match pane {
+            0 => &mut self.parent1,
+            1 => &mut self.parent2,
+            _ => &mut self.result,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    static CONFLICT: &str = "diff --cc x
+index 1111111,2222222..3333333
+--- a/x
++++ b/x
+@@@ -1,3 -1,3 +1,3 @@@
+  a
+- let left = 1;
+ -let right = 2;
+++let right = 1;
+  c
+";
+
+    #[test]
+    fn highlights_results_against_both_parents() {
+        let printer = ThreeWayPrinter::new(Diff2HtmlConfig::default());
+        let file = parse::parse_diff(CONFLICT).remove(0);
+        let html = printer.generate_file_html(&file);
+
+        // Each parent shows its own line, changed against the result.
+        assert!(html.parent1.contains("<del>left</del>"));
+        assert!(!html.parent1.contains("let right = 2;"));
+        assert!(html.parent2.contains("<del>2</del>"));
+        assert!(!html.parent2.contains("left"));
+        // The result differs from the first parent in its name and from the
+        // second one in its value.
+        assert!(html.result.contains("<ins>right</ins>"));
+        assert!(html.result.contains("<ins>1</ins>"));
+        assert!(!html.result.contains("<ins>let</ins>"));
+    }
+
+    #[test]
+    fn renders_other_files_side_by_side() {
+        let config = Diff2HtmlConfig::default();
+        let three_way = ThreeWayPrinter::new(config.clone());
+        let side_by_side = SideBySidePrinter::new(config);
+
+        let file = parse::parse_diff("--- a/x\n+++ b/x\n@@ -1 +1 @@\n-a\n+b\n").remove(0);
+        assert_eq!(
+            three_way.render_file(&file),
+            side_by_side.render_file(&file)
+        );
+
+        // Octopus merges have more parents than there are panes.
+        let file =
+            parse::parse_diff("diff --cc y\n--- a/y\n+++ b/y\n@@@@ -1 -1 -1 +1 @@@@\n---a\n+++b\n")
+                .remove(0);
+        assert_eq!(
+            three_way.render_file(&file),
+            side_by_side.render_file(&file)
+        );
+
+        let file = parse::parse_diff(CONFLICT).remove(0);
+        let html = three_way.render_file(&file);
+        assert_eq!(html.matches("d2h-file-three-diff").count(), 3);
+    }
+}
@@ -6,7 +6,7 @@ use seahash;
 use v_htmlescape::escape;
 
 use crate::config::Diff2HtmlConfig;
-use crate::difference::{Changeset, SplitType};
+use crate::difference::{Algorithm, Changeset, SplitType};
 use crate::parse;
 
 pub mod rematch;
@@ -21,6 +21,7 @@ static TAG_FILE_ADDED: &'static str = include_str!("../../templates/tag-file-add
 static TAG_FILE_CHANGED: &'static str = include_str!("../../templates/tag-file-changed.hbs");
 static TAG_FILE_DELETED: &'static str = include_str!("../../templates/tag-file-deleted.hbs");
 static TAG_FILE_RENAMED: &'static str = include_str!("../../templates/tag-file-renamed.hbs");
+static TAG_FILE_RESOLVED: &str = include_str!("../../templates/tag-file-resolved.hbs");
 
 pub fn get_html_id(file: &parse::File) -> String {
     let diff_name = get_diff_name(file);
@@ -166,16 +167,18 @@ impl Hash for Difference {
     }
 }
 
-pub fn get_line_matcher() -> rematch::Rematcher<parse::Line> {
-    rematch::Rematcher::new(|a: &parse::Line, b: &parse::Line| {
-        let amod = &a.content[1..];
-        let bmod = &b.content[1..];
-        rematch::distance(amod, bmod)
+pub fn get_line_matcher(config: &Diff2HtmlConfig) -> rematch::Rematcher<parse::Line> {
+    let config = config.to_owned();
+    rematch::Rematcher::new(move |a: &parse::Line, b: &parse::Line| {
+        let amod = normalize(&config, &a.content[a.parents.len().max(1)..]);
+        let bmod = normalize(&config, &b.content[b.parents.len().max(1)..]);
+        rematch::distance(&amod, &bmod)
     })
 }
 
-pub fn get_difference_matcher() -> rematch::Rematcher<Difference> {
-    rematch::Rematcher::new(|a: &Difference, b: &Difference| {
+pub fn get_difference_matcher(config: &Diff2HtmlConfig) -> rematch::Rematcher<Difference> {
+    let config = config.to_owned();
+    rematch::Rematcher::new(move |a: &Difference, b: &Difference| {
         let amod = match &a.0 {
             difference::Difference::Same(content)
             | difference::Difference::Add(content)
@@ -186,20 +189,118 @@ pub fn get_difference_matcher() -> rematch::Rematcher<Difference> {
             | difference::Difference::Add(content)
             | difference::Difference::Rem(content) => content,
         };
-        rematch::distance(&amod, &bmod)
+        rematch::distance(&normalize(&config, amod), &normalize(&config, bmod))
     })
 }
 
+/// The text of a line as compared with `ignore_whitespace` and `ignore_case`.
+pub fn normalize(config: &Diff2HtmlConfig, line: &str) -> String {
+    normalize_part(config, line, true)
+}
+
+// Like `normalize`, for a part of a line that may not be at its end.
+fn normalize_part(config: &Diff2HtmlConfig, text: &str, at_eol: bool) -> String {
+    let text = match config.ignore_whitespace.as_str() {
+        "all" => text.chars().filter(|c| !c.is_whitespace()).collect(),
+        "change" => {
+            let mut collapsed = String::new();
+            for c in text.chars() {
+                if !c.is_whitespace() {
+                    collapsed.push(c);
+                } else if !collapsed.ends_with(' ') {
+                    collapsed.push(' ');
+                }
+            }
+            if at_eol {
+                collapsed.trim_end().to_owned()
+            } else {
+                collapsed
+            }
+        }
+        "eol" if at_eol => text.trim_end().to_owned(),
+        _ => text.to_owned(),
+    };
+
+    if config.ignore_case {
+        text.to_lowercase()
+    } else {
+        text
+    }
+}
+
+/*
+ * Which changes between two lines are ones the config ignores. Removed
+ * parts are followed by the added ones they were replaced with, and a change
+ * is at the end of the line when only whitespace follows it.
+ */
+fn get_ignored_changes(config: &Diff2HtmlConfig, diffs: &[Difference]) -> Vec<bool> {
+    let mut ignored = vec![false; diffs.len()];
+    if config.ignore_whitespace == "none" && !config.ignore_case {
+        return ignored;
+    }
+
+    let mut start = 0;
+    while start < diffs.len() {
+        let end = diffs[start..]
+            .iter()
+            .position(|diff| matches!(diff.0, difference::Difference::Same(_)))
+            .map_or(diffs.len(), |position| start + position);
+        if end == start {
+            start += 1;
+            continue;
+        }
+
+        // Whitespace around the change counts too, so that a space added
+        // next to another one is only a change in the amount of whitespace.
+        let before = match start.checked_sub(1).map(|i| &diffs[i].0) {
+            Some(difference::Difference::Same(content)) => &content[content.trim_end().len()..],
+            _ => "",
+        };
+        let after = match diffs.get(end).map(|diff| &diff.0) {
+            Some(difference::Difference::Same(content)) => {
+                &content[..content.len() - content.trim_start().len()]
+            }
+            _ => "",
+        };
+
+        let mut removed = before.to_owned();
+        let mut added = before.to_owned();
+        for diff in diffs[start..end].iter() {
+            match &diff.0 {
+                difference::Difference::Rem(content) => removed.push_str(content),
+                difference::Difference::Add(content) => added.push_str(content),
+                _ => {}
+            }
+        }
+        removed.push_str(after);
+        added.push_str(after);
+        let at_eol = diffs[end..].iter().all(|diff| match &diff.0 {
+            difference::Difference::Same(content) => content.trim().is_empty(),
+            _ => false,
+        });
+
+        if normalize_part(config, &removed, at_eol) == normalize_part(config, &added, at_eol) {
+            for is_ignored in ignored[start..end].iter_mut() {
+                *is_ignored = true;
+            }
+        }
+        start = end;
+    }
+
+    ignored
+}
+
 pub fn diff_highlight<'a>(
     config: &Diff2HtmlConfig,
     matcher: Option<&rematch::Rematcher<Difference>>,
+    prefix_size: usize,
     diff_line1: &'a str,
     diff_line2: &'a str,
 ) -> Highlighted<'a> {
     // TODO: idk
     let mut matcher = matcher;
     let matcher_alt = if matcher.is_none() {
-        Some(get_difference_matcher())
+        Some(get_difference_matcher(config))
     } else {
         None
     };
@@ -212,8 +313,6 @@ pub fn diff_highlight<'a>(
     let unprefixed_line1;
     let unprefixed_line2;
 
-    let prefix_size = if config.is_combined { 2 } else { 1 };
-
     line_prefix1 = &diff_line1[0..prefix_size];
     line_prefix2 = &diff_line2[0..prefix_size];
     unprefixed_line1 = &diff_line1[prefix_size..];
@@ -236,15 +335,14 @@ pub fn diff_highlight<'a>(
         };
     }
 
-    let diffs: Vec<Difference> = if !config.char_by_char || config.diff == "smartword" {
-        Changeset::new(unprefixed_line1, unprefixed_line2, &SplitType::SmartWord)
-    } else {
-        Changeset::new(unprefixed_line1, unprefixed_line2, &SplitType::Character)
-    }
-    .diffs
-    .drain(..)
-    .map(|v| Difference(v))
-    .collect();
+    let split = get_split(config);
+    let algorithm = Algorithm::from_name(&config.diff_algorithm);
+    let diffs: Vec<Difference> =
+        Changeset::with_algorithm(unprefixed_line1, unprefixed_line2, &split, &algorithm)
+            .diffs
+            .drain(..)
+            .map(Difference)
+            .collect();
 
     let mut changed_words = Vec::new();
     if (!config.char_by_char || config.diff == "smartword") && config.matching == "words" {
@@ -289,41 +387,39 @@ pub fn diff_highlight<'a>(
         });
     }
 
+    let ignored = get_ignored_changes(config, &diffs);
     let mut delete_line = Vec::new();
     let mut insert_line = Vec::new();
-    diffs.iter().for_each(|part| {
-        let add_class = if changed_words.contains(&part) {
-            r#" class="d2h-change""#
-        } else {
-            ""
-        };
+    diffs
+        .iter()
+        .zip(ignored.iter())
+        .for_each(|(part, is_ignored)| {
+            let add_class = if changed_words.contains(part) {
+                r#" class="d2h-change""#
+            } else {
+                ""
+            };
 
-        match &part.0 {
-            difference::Difference::Add(ref s) => {
-                insert_line.push(format!(
-                    "<{}{}>{}</{}>",
-                    "ins",
-                    add_class,
-                    escape(s).to_string(),
-                    "ins"
-                ));
-            }
-            difference::Difference::Rem(ref s) => {
-                delete_line.push(format!(
-                    "<{}{}>{}</{}>",
-                    "del",
-                    add_class,
-                    escape(s).to_string(),
-                    "del"
-                ));
-            }
-            difference::Difference::Same(ref s) => {
-                let escaped = escape(s).to_string();
-                insert_line.push(escaped.to_owned());
-                delete_line.push(escaped);
-            }
-        };
-    });
+            match &part.0 {
+                difference::Difference::Add(ref s) if *is_ignored => {
+                    insert_line.push(format!(r#"<span class="d2h-ignored">{}</span>"#, escape(s)));
+                }
+                difference::Difference::Rem(ref s) if *is_ignored => {
+                    delete_line.push(format!(r#"<span class="d2h-ignored">{}</span>"#, escape(s)));
+                }
+                difference::Difference::Add(ref s) => {
+                    insert_line.push(format!("<{}{}>{}</{}>", "ins", add_class, escape(s), "ins"));
+                }
+                difference::Difference::Rem(ref s) => {
+                    delete_line.push(format!("<{}{}>{}</{}>", "del", add_class, escape(s), "del"));
+                }
+                difference::Difference::Same(ref s) => {
+                    let escaped = escape(s).to_string();
+                    insert_line.push(escaped.to_owned());
+                    delete_line.push(escaped);
+                }
+            };
+        });
 
     let join = if !config.char_by_char { " " } else { "" };
     let delete_line = delete_line.join(join);
@@ -341,6 +437,103 @@ pub fn diff_highlight<'a>(
     }
 }
 
+/// Marks the parts of `new_line` that differ from any of `old_lines`, like
+/// the inserted side of `diff_highlight`, which it is with a single old line.
+pub fn diff_highlight_all(
+    config: &Diff2HtmlConfig,
+    matcher: Option<&rematch::Rematcher<Difference>>,
+    prefix_size: usize,
+    old_lines: &[&str],
+    new_line: &str,
+) -> String {
+    if let [old_line] = old_lines {
+        return diff_highlight(config, matcher, prefix_size, old_line, new_line)
+            .second
+            .line;
+    }
+
+    let unprefixed_line = &new_line[prefix_size..];
+    if unprefixed_line.len() > config.max_line_length_highlight {
+        return escape(unprefixed_line).to_string();
+    }
+
+    let split = get_split(config);
+    let algorithm = Algorithm::from_name(&config.diff_algorithm);
+    // Whether every byte of the line was added, and where the parts end.
+    let mut added = vec![false; unprefixed_line.len()];
+    let mut ends = vec![unprefixed_line.len()];
+    for old_line in old_lines.iter() {
+        let unprefixed_old_line = &old_line[prefix_size..];
+        if unprefixed_old_line.len() > config.max_line_length_highlight {
+            continue;
+        }
+        let mut position = 0;
+        let changeset =
+            Changeset::with_algorithm(unprefixed_old_line, unprefixed_line, &split, &algorithm);
+        for diff in changeset.diffs.iter() {
+            let (part, is_added) = match diff {
+                difference::Difference::Same(part) => (part, false),
+                difference::Difference::Add(part) => (part, true),
+                difference::Difference::Rem(_) => continue,
+            };
+            let end = (position + part.len()).min(added.len());
+            for byte in added[position..end].iter_mut() {
+                *byte |= is_added;
+            }
+            position = end;
+            ends.push(position);
+        }
+    }
+    ends.sort_unstable();
+    ends.dedup();
+
+    let mut parts: Vec<(bool, usize, usize)> = Vec::new();
+    let mut start = 0;
+    for &end in ends.iter().filter(|&&end| end > 0) {
+        match parts.last_mut() {
+            Some(part) if part.0 == added[start] => part.2 = end,
+            _ => parts.push((added[start], start, end)),
+        }
+        start = end;
+    }
+
+    let join = if !config.char_by_char { " " } else { "" };
+    parts
+        .iter()
+        .map(|&(is_added, start, end)| {
+            let part = escape(&unprefixed_line[start..end]);
+            if is_added {
+                format!("<ins>{}</ins>", part)
+            } else {
+                part.to_string()
+            }
+        })
+        .collect::<Vec<String>>()
+        .join(join)
+}
+
+fn get_split(config: &Diff2HtmlConfig) -> SplitType {
+    if !config.char_by_char || config.diff == "smartword" {
+        SplitType::SmartWord
+    } else {
+        SplitType::Character
+    }
+}
+
+/// Words of a word diff line, as `diff_highlight` would mark them, leaving
+/// out the words of the `hidden` type.
+pub fn get_segments_html(segments: &[parse::Segment], hidden: Option<&parse::LineType>) -> String {
+    segments
+        .iter()
+        .filter(|segment| Some(&segment.line_type) != hidden)
+        .map(|segment| match segment.line_type {
+            parse::LineType::Inserts => format!("<ins>{}</ins>", escape(&segment.content)),
+            parse::LineType::Deletes => format!("<del>{}</del>", escape(&segment.content)),
+            _ => escape(&segment.content).to_string(),
+        })
+        .collect()
+}
+
 pub struct Highlighted<'a> {
     pub first: HighlightedLine<'a>,
     pub second: HighlightedLine<'a>,
@@ -356,25 +549,30 @@ pub struct SeparatedLine<'a> {
     pub line: &'a str,
 }
 
-pub fn separate_prefix<'a>(is_combined: bool, line: &'a str) -> SeparatedLine<'a> {
+pub fn separate_prefix<'a>(prefix_size: usize, line: &'a str) -> SeparatedLine<'a> {
     if line == "" {
         SeparatedLine {
             prefix: "",
             line: "",
         }
-    } else if is_combined {
-        SeparatedLine {
-            prefix: &line[0..2],
-            line: &line[2..],
-        }
     } else {
         SeparatedLine {
-            prefix: &line[0..1],
-            line: &line[1..],
+            prefix: &line[0..prefix_size],
+            line: &line[prefix_size..],
         }
     }
 }
 
+// Combined diffs have one prefix column per parent.
+pub fn get_prefix_size(file: &parse::File) -> usize {
+    file.blocks
+        .iter()
+        .map(|block| block.parent_ranges.len())
+        .max()
+        .unwrap_or(0)
+        .max(1)
+}
+
 pub fn get_file_type_icon(file: &parse::File) -> &str {
     let mut partial = ICON_FILE_CHANGED;
 
@@ -424,6 +622,45 @@ pub fn get_line_type_tag(file: &parse::File) -> &str {
     partial
 }
 
+// A line added against every parent of a merge was written by hand while
+// resolving a conflict.
+pub fn has_manual_resolution(file: &parse::File) -> bool {
+    file.blocks.iter().any(|block| {
+        block.lines.iter().any(|line| {
+            line.parents.len() > 1
+                && line
+                    .parents
+                    .iter()
+                    .all(|parent| parent.line_type == parse::LineType::Inserts)
+        })
+    })
+}
+
+pub fn get_resolution_tag(file: &parse::File) -> &str {
+    if has_manual_resolution(file) {
+        TAG_FILE_RESOLVED
+    } else {
+        ""
+    }
+}
+
+pub fn get_size_delta(file: &parse::File) -> Option<String> {
+    match (file.old_size, file.new_size) {
+        (Some(old_size), Some(new_size)) if new_size >= old_size => {
+            Some(format!("+{} B", new_size - old_size))
+        }
+        (Some(old_size), Some(new_size)) => Some(format!("-{} B", old_size - new_size)),
+        _ => None,
+    }
+}
+
+pub fn get_size_change(file: &parse::File) -> Option<String> {
+    match (file.old_size, file.new_size) {
+        (Some(old_size), Some(new_size)) => Some(format!("{} B → {} B", old_size, new_size)),
+        _ => None,
+    }
+}
+
 pub fn make_column_line_number_html(
     handlebars: &Handlebars,
     header: &str,
@@ -452,3 +689,119 @@ pub fn generate_empty_diff(handlebars: &Handlebars, content_class: &str) -> Stri
         )
         .unwrap()
 }
+
+/// Describes a submodule update like "submodule lib: 1fb4d55 → c2d9714".
+pub fn get_submodule_change(file: &parse::File) -> Option<String> {
+    let short_commit = |commit: &Option<String>| match commit {
+        Some(commit) => commit.chars().take(7).collect(),
+        _ => "0000000".to_owned(),
+    };
+
+    file.submodule.as_ref().map(|submodule| {
+        format!(
+            "submodule {}: {} → {}{}",
+            get_diff_name(file),
+            short_commit(&submodule.old_commit),
+            short_commit(&submodule.new_commit),
+            if submodule.is_dirty { "-dirty" } else { "" }
+        )
+    })
+}
+
+pub fn generate_submodule_diff(
+    handlebars: &Handlebars,
+    file: &parse::File,
+    content_class: &str,
+) -> String {
+    handlebars
+        .render(
+            "generic-submodule-diff",
+            &json!({
+                "contentClass": content_class,
+                "submoduleChange": get_submodule_change(file),
+            }),
+        )
+        .unwrap()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn is_highlighted(config: &Diff2HtmlConfig, old_line: &str, new_line: &str) -> bool {
+        let highlighted = diff_highlight(config, None, 1, old_line, new_line);
+        highlighted.first.line.contains("<del>") || highlighted.second.line.contains("<ins>")
+    }
+
+    #[test]
+    fn ignores_changes_the_config_asks_to() {
+        let cases = [
+            ("all", false, "-let x=1;", "+let x = 1;"),
+            ("change", false, "-let  x =  1;", "+let x = 1;"),
+            ("eol", false, "-let x = 1;", "+let x = 1;   "),
+            ("none", true, "-Let X = 1;", "+let x = 1;"),
+        ];
+        for &(ignore_whitespace, ignore_case, old_line, new_line) in cases.iter() {
+            let mut config = Diff2HtmlConfig::default();
+            assert!(is_highlighted(&config, old_line, new_line));
+
+            config.ignore_whitespace = ignore_whitespace.to_owned();
+            config.ignore_case = ignore_case;
+            assert!(
+                !is_highlighted(&config, old_line, new_line),
+                "{} {}",
+                ignore_whitespace,
+                ignore_case
+            );
+            assert_eq!(
+                normalize(&config, &old_line[1..]),
+                normalize(&config, &new_line[1..])
+            );
+
+            // Other changes on the line are still shown.
+            assert!(is_highlighted(&config, old_line, "+let y = 2;"));
+        }
+    }
+
+    #[test]
+    fn keeps_whitespace_changes_the_mode_does_not_cover() {
+        let mut config = Diff2HtmlConfig {
+            ignore_whitespace: "change".to_owned(),
+            ..Diff2HtmlConfig::default()
+        };
+        assert!(is_highlighted(&config, "-let x=1;", "+let x = 1;"));
+
+        config.ignore_whitespace = "eol".to_owned();
+        assert!(is_highlighted(&config, "-let  x = 1;", "+let x = 1;"));
+    }
+
+    #[test]
+    fn marks_word_diff_segments() {
+        let segments = vec![
+            parse::Segment {
+                line_type: parse::LineType::Context,
+                content: "let ".to_owned(),
+            },
+            parse::Segment {
+                line_type: parse::LineType::Deletes,
+                content: "a<b".to_owned(),
+            },
+            parse::Segment {
+                line_type: parse::LineType::Inserts,
+                content: "c".to_owned(),
+            },
+        ];
+        assert_eq!(
+            get_segments_html(&segments, None),
+            "let <del>a&lt;b</del><ins>c</ins>"
+        );
+        assert_eq!(
+            get_segments_html(&segments, Some(&parse::LineType::Deletes)),
+            "let <ins>c</ins>"
+        );
+        assert_eq!(
+            get_segments_html(&segments, Some(&parse::LineType::Inserts)),
+            "let <del>a&lt;b</del>"
+        );
+    }
+}
@@ -12,14 +12,16 @@ pub struct Match {
     _score: f64,
 }
 
+type DistanceFunction<T> = Box<dyn Fn(&T, &T) -> f64>;
+
 pub struct Rematcher<T: Hash + Debug> {
-    distance_function: fn(&T, &T) -> f64,
+    distance_function: DistanceFunction<T>,
 }
 
 impl<T: Hash + Debug> Rematcher<T> {
-    pub fn new(distance_function: fn(&T, &T) -> f64) -> Rematcher<T> {
+    pub fn new<F: Fn(&T, &T) -> f64 + 'static>(distance_function: F) -> Rematcher<T> {
         Rematcher {
-            distance_function: distance_function,
+            distance_function: Box::new(distance_function),
         }
     }
 
@@ -36,7 +38,7 @@ impl<T: Hash + Debug> Rematcher<T> {
                 let md = if cache.contains_key(&cache_key) {
                     cache[&cache_key]
                 } else {
-                    let distance_function = self.distance_function;
+                    let distance_function = &self.distance_function;
                     cache.insert(cache_key, distance_function(&a[i], &b[j]));
                     cache[&cache_key]
                 };
@@ -72,7 +74,7 @@ impl<T: Hash + Debug> Rematcher<T> {
                 let md = if cache.contains_key(&cache_key) {
                     cache[&cache_key]
                 } else {
-                    let distance_function = self.distance_function;
+                    let distance_function = &self.distance_function;
                     cache.insert(cache_key, distance_function(&a[i], &b[j]));
                     cache[&cache_key]
                 };